@@ -0,0 +1,126 @@
+use super::*;
+use crate::Joint;
+use std::collections::{BTreeMap, BTreeSet};
+
+impl<T> TaxicabMap<T> {
+    /// Get all passable points whose removal disconnects the passable points around them.
+    ///
+    /// Steps follow the topology and portals, the graph is treated as undirected.
+    pub fn articulation_points<F>(&self, passable: F) -> BTreeSet<(isize, isize)>
+    where
+        F: Fn(isize, isize, &T) -> bool,
+    {
+        self.critical(passable).0
+    }
+    /// Get all joints whose removal disconnects the passable points at their two sides.
+    ///
+    /// Each bridge is reported once, from the side reached first.
+    pub fn bridges<F>(&self, passable: F) -> BTreeSet<Joint>
+    where
+        F: Fn(isize, isize, &T) -> bool,
+    {
+        self.critical(passable).1
+    }
+    /// Tarjan's low-link search, iterative so large maps do not overflow the stack.
+    fn critical<F>(&self, passable: F) -> (BTreeSet<(isize, isize)>, BTreeSet<Joint>)
+    where
+        F: Fn(isize, isize, &T) -> bool,
+    {
+        let nodes = self.points_all().filter(|(x, y, v)| passable(*x, *y, v)).map(|(x, y, _)| (x, y)).collect::<Vec<_>>();
+        let index = nodes.iter().enumerate().map(|(i, p)| (*p, i)).collect::<BTreeMap<_, _>>();
+        // both sides of an edge share the key, parallel edges across narrow seams stay distinct
+        let mut edges = BTreeMap::new();
+        for (a, (x, y)) in nodes.iter().enumerate() {
+            for joint in self.joints_nearby(*x, *y) {
+                let (Some(from), Some(to)) = (self.wrap_joint(joint), self.joint_target(joint))
+                else {
+                    continue;
+                };
+                let b = match index.get(&to.source()) {
+                    Some(b) if *b != a => *b,
+                    _ => continue,
+                };
+                let key = if from < !to { (from, !to) } else { (!to, from) };
+                edges.entry(key).or_insert((a, b, from, !to));
+            }
+        }
+        let edges = edges.into_values().collect::<Vec<_>>();
+        let mut adjacent = vec![vec![]; nodes.len()];
+        for (e, (a, b, _, _)) in edges.iter().enumerate() {
+            adjacent[*a].push((*b, e));
+            adjacent[*b].push((*a, e));
+        }
+        let mut order = vec![usize::MAX; nodes.len()];
+        let mut low = vec![usize::MAX; nodes.len()];
+        let mut points = BTreeSet::new();
+        let mut bridges = BTreeSet::new();
+        let mut counter = 0;
+        for root in 0..nodes.len() {
+            if order[root] != usize::MAX {
+                continue;
+            }
+            order[root] = counter;
+            low[root] = counter;
+            counter += 1;
+            let mut children = 0;
+            // (node, edge to parent, next adjacent index)
+            let mut stack = vec![(root, usize::MAX, 0)];
+            while let Some(&mut (node, parent, ref mut next)) = stack.last_mut() {
+                if let Some(&(other, e)) = adjacent[node].get(*next) {
+                    *next += 1;
+                    if e == parent {
+                        continue;
+                    }
+                    if order[other] == usize::MAX {
+                        order[other] = counter;
+                        low[other] = counter;
+                        counter += 1;
+                        if node == root {
+                            children += 1;
+                        }
+                        stack.push((other, e, 0));
+                    }
+                    else {
+                        low[node] = low[node].min(order[other]);
+                    }
+                    continue;
+                }
+                stack.pop();
+                if let Some(&(up, _, _)) = stack.last() {
+                    low[up] = low[up].min(low[node]);
+                    if low[node] > order[up] {
+                        let (a, _, forward, backward) = edges[parent];
+                        bridges.insert(if a == up { forward } else { backward });
+                    }
+                    if up != root && low[node] >= order[up] {
+                        points.insert(nodes[up]);
+                    }
+                }
+            }
+            if children > 1 {
+                points.insert(nodes[root]);
+            }
+        }
+        (points, bridges)
+    }
+}
+
+impl TaxicabMap<Option<usize>> {
+    /// Get the width of the border between each pair of labeled regions, counted in joints.
+    ///
+    /// The key is the pair of labels with the lower one first, narrow borders are chokepoints.
+    pub fn border_widths(&self) -> BTreeMap<(usize, usize), usize> {
+        let mut out = BTreeMap::new();
+        for joint in self.region_borders() {
+            let target =
+                self.joint_target(joint).and_then(|to| self.get_point(to.source().0, to.source().1).copied().flatten());
+            let source = self.get_point(joint.source().0, joint.source().1).copied().flatten();
+            if let (Some(a), Some(b)) = (source, target) {
+                if a < b {
+                    *out.entry((a, b)).or_insert(0) += 1;
+                }
+            }
+        }
+        out
+    }
+}
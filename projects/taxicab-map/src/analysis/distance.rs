@@ -0,0 +1,82 @@
+use super::*;
+
+impl<T> TaxicabMap<T> {
+    /// Get the taxicab distance between two points, going across the seam of cyclic axes when it is shorter.
+    pub fn taxicab_distance(&self, start: (isize, isize), end: (isize, isize)) -> usize {
+        let (w, h) = self.get_size();
+        let (cycle_x, cycle_y) = self.get_cycle();
+        axis_distance(start.0, end.0, w, cycle_x) + axis_distance(start.1, end.1, h, cycle_y)
+    }
+    /// Get the taxicab distance from each point to the nearest feature point, by the two pass transform in `O(w·h)`.
+    ///
+    /// Points get `usize::MAX` if there is no feature. The distance is geometric, holes do not block it.
+    /// Cyclic axes are wrapped, twists are not followed.
+    pub fn distance_transform<F>(&self, is_feature: F) -> TaxicabMap<usize>
+    where
+        F: Fn(isize, isize, &T) -> bool,
+    {
+        let (cycle_x, cycle_y) = self.get_cycle();
+        let mut out = self.map_values(|x, y, v| if self.has_point(x, y) && is_feature(x, y, v) { 0 } else { usize::MAX });
+        let (w, h) = out.get_size();
+        // taxicab distance is separable, transform rows first then columns
+        for y in 0..h {
+            let mut line = (0..w).map(|x| out.get_relative_value(x, y)).collect::<Vec<_>>();
+            sweep(&mut line, cycle_x);
+            for (x, v) in line.into_iter().enumerate() {
+                out.set_relative_value(x, y, v);
+            }
+        }
+        for x in 0..w {
+            let mut line = (0..h).map(|y| out.get_relative_value(x, y)).collect::<Vec<_>>();
+            sweep(&mut line, cycle_y);
+            for (y, v) in line.into_iter().enumerate() {
+                out.set_relative_value(x, y, v);
+            }
+        }
+        out
+    }
+    /// Get the signed distance, positive outside of the features and negative inside.
+    ///
+    /// Points outside get the distance to the nearest feature, points inside get the negative distance to the
+    /// nearest non-feature point. See [`TaxicabMap::distance_transform`].
+    pub fn signed_distance_transform<F>(&self, is_feature: F) -> TaxicabMap<isize>
+    where
+        F: Fn(isize, isize, &T) -> bool,
+    {
+        let outside = self.distance_transform(&is_feature);
+        let inside = self.distance_transform(|x, y, v| !is_feature(x, y, v));
+        outside.map_values(|x, y, d| match *d {
+            0 => -clamp(inside.get_point(x, y).copied().unwrap_or(usize::MAX)),
+            d => clamp(d),
+        })
+    }
+}
+
+fn axis_distance(a: isize, b: isize, size: usize, cyclic: bool) -> usize {
+    let d = a.abs_diff(b);
+    match cyclic && size > 0 {
+        true => {
+            let d = d % size;
+            d.min(size - d)
+        }
+        false => d,
+    }
+}
+
+fn clamp(v: usize) -> isize {
+    v.min(isize::MAX as usize) as isize
+}
+
+/// 1D taxicab distance transform, cyclic lines go around twice so distances cross the seam.
+fn sweep(line: &mut [usize], cyclic: bool) {
+    let n = line.len();
+    let rounds = if cyclic { 2 } else { 1 };
+    for k in 1..n * rounds {
+        let (i, p) = (k % n, (k - 1) % n);
+        line[i] = line[i].min(line[p].saturating_add(1));
+    }
+    for k in 1..n * rounds {
+        let (i, p) = (n - 1 - k % n, n - 1 - (k - 1) % n);
+        line[i] = line[i].min(line[p].saturating_add(1));
+    }
+}
@@ -0,0 +1,96 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// How the strength of an influence source fades over distance.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Decay {
+    /// Lose a fixed amount per step, never below zero
+    Linear(f64),
+    /// Keep a fixed factor per step, `0.5` halves the strength each step
+    Exponential(f64),
+}
+
+/// How two influence maps are combined point by point.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Combine {
+    /// Sum of both values
+    Add,
+    /// The larger value
+    Max,
+    /// The smaller value
+    Min,
+}
+
+impl Decay {
+    /// Get the strength left after the given distance or path cost.
+    pub fn falloff(&self, strength: f64, distance: f64) -> f64 {
+        match *self {
+            Decay::Linear(rate) => {
+                let left = strength.abs() - rate * distance;
+                if left > 0.0 { left.copysign(strength) } else { 0.0 }
+            }
+            Decay::Exponential(factor) => strength * factor.powf(distance),
+        }
+    }
+}
+
+impl TaxicabMap<f64> {
+    /// Add an influence source at the point, fading over the taxicab distance.
+    ///
+    /// Negative strength stamps a threat, see [`TaxicabMap::taxicab_distance`] for the distance.
+    pub fn stamp(&mut self, x: isize, y: isize, strength: f64, decay: Decay) {
+        let distance = self.map_values(|px, py, _| self.taxicab_distance((x, y), (px, py)));
+        for (px, py, v) in self.points_mut() {
+            *v += decay.falloff(strength, distance[(px, py)] as f64);
+        }
+    }
+    /// Add an influence source fading over the path cost of a field.
+    ///
+    /// The field is usually an [`ActionFieldSolver`](crate::ActionFieldSolver) solved on the terrain,
+    /// points the field does not reach are not affected.
+    pub fn stamp_field<I>(&mut self, field: I, strength: f64, decay: Decay)
+    where
+        I: IntoIterator<Item = (f64, (isize, isize))>,
+    {
+        for (cost, (x, y)) in field {
+            if let Some(v) = self.mut_point(x, y) {
+                *v += decay.falloff(strength, cost)
+            }
+        }
+    }
+    /// Combine another influence map into this one, points are matched by absolute coordinates.
+    ///
+    /// Points missing in the other map are kept as they are.
+    pub fn combine(&mut self, other: &TaxicabMap<f64>, mode: Combine) {
+        for (x, y, v) in self.points_mut() {
+            if let Some(o) = other.get_point(x, y) {
+                *v = match mode {
+                    Combine::Add => *v + o,
+                    Combine::Max => v.max(*o),
+                    Combine::Min => v.min(*o),
+                }
+            }
+        }
+    }
+    /// Rescale all values into `0.0..=1.0`, a flat map becomes all zero.
+    pub fn normalize(&mut self) {
+        let (min, max) =
+            self.points_all().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, _, v)| (min.min(*v), max.max(*v)));
+        let span = max - min;
+        for (_, _, v) in self.points_mut() {
+            *v = if span > 0.0 { (*v - min) / span } else { 0.0 }
+        }
+    }
+    /// Get the point with the highest value in the region, ties go to the lowest x then the lowest y.
+    pub fn best_point(&self, x: Range<isize>, y: Range<isize>) -> Option<(isize, isize)> {
+        let mut best: Option<((isize, isize), f64)> = None;
+        for (px, py) in x.flat_map(|px| y.clone().map(move |py| (px, py))) {
+            match self.get_point(px, py) {
+                Some(v) if best.is_none_or(|(_, b)| *v > b) => best = Some(((px, py), *v)),
+                _ => {}
+            }
+        }
+        best.map(|(p, _)| p)
+    }
+}
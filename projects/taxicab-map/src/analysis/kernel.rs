@@ -0,0 +1,117 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+/// Weighted offsets sampled around each point, also used as a structuring element.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Kernel {
+    weights: Vec<(isize, isize, f64)>,
+}
+
+/// How a kernel samples points beyond the edges of the map, cyclic axes always wrap.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Boundary<T> {
+    /// Use the nearest point on the edge
+    Clamp,
+    /// Continue from the opposite edge, as if the axis were cyclic
+    Wrap,
+    /// Use a fixed value
+    Constant(T),
+}
+
+impl Kernel {
+    /// Create a kernel from the offsets and weights.
+    pub fn new<I>(weights: I) -> Self
+    where
+        I: IntoIterator<Item = (isize, isize, f64)>,
+    {
+        Self { weights: weights.into_iter().collect() }
+    }
+    /// Create a kernel of all points within the taxicab radius, with weight 1.
+    pub fn diamond(radius: usize) -> Self {
+        let r = radius as isize;
+        Self::new(square(r).filter(|(x, y)| x.abs() + y.abs() <= r).map(|(x, y)| (x, y, 1.0)))
+    }
+    /// Create a kernel of all points on the axes within the radius, with weight 1.
+    pub fn cross(radius: usize) -> Self {
+        Self::new(square(radius as isize).filter(|(x, y)| *x == 0 || *y == 0).map(|(x, y)| (x, y, 1.0)))
+    }
+    /// Scale the weights so they sum to 1.
+    pub fn normalized(mut self) -> Self {
+        let sum: f64 = self.weights.iter().map(|(_, _, w)| w).sum();
+        if sum != 0.0 {
+            self.weights.iter_mut().for_each(|(_, _, w)| *w /= sum);
+        }
+        self
+    }
+    /// Get the offsets and weights of the kernel.
+    pub fn weights(&self) -> &[(isize, isize, f64)] {
+        &self.weights
+    }
+}
+
+fn square(r: isize) -> impl Iterator<Item = (isize, isize)> {
+    (-r..=r).flat_map(move |x| (-r..=r).map(move |y| (x, y)))
+}
+
+impl<T> TaxicabMap<T> {
+    /// Apply a kernel to each point, the function receives the value of the point and the weighted samples around it.
+    ///
+    /// Samples falling into holes are skipped unless the boundary is a constant.
+    pub fn apply_kernel<U, F>(&self, kernel: &Kernel, boundary: &Boundary<T>, f: F) -> TaxicabMap<U>
+    where
+        F: Fn(isize, isize, &T, &[(f64, &T)]) -> U,
+    {
+        let (x_range, y_range) = self.get_range();
+        let mut samples = Vec::with_capacity(kernel.weights.len());
+        self.map_values(|x, y, v| {
+            samples.clear();
+            for &(dx, dy, w) in &kernel.weights {
+                let (sx, sy) = (x + dx, y + dy);
+                let sample = self.get_point(sx, sy).or_else(|| match boundary {
+                    Boundary::Clamp => {
+                        self.get_point(sx.clamp(x_range.start, x_range.end - 1), sy.clamp(y_range.start, y_range.end - 1))
+                    }
+                    Boundary::Wrap => self.get_point(
+                        x_range.start + (sx - x_range.start).rem_euclid(x_range.len().max(1) as isize),
+                        y_range.start + (sy - y_range.start).rem_euclid(y_range.len().max(1) as isize),
+                    ),
+                    Boundary::Constant(c) => Some(c),
+                });
+                if let Some(s) = sample {
+                    samples.push((w, s))
+                }
+            }
+            f(x, y, v, &samples)
+        })
+    }
+}
+
+impl TaxicabMap<f64> {
+    /// Get the weighted sum of the kernel samples at each point.
+    pub fn convolve(&self, kernel: &Kernel, boundary: &Boundary<f64>) -> TaxicabMap<f64> {
+        self.apply_kernel(kernel, boundary, |_, _, _, samples| samples.iter().map(|(w, v)| w * **v).sum())
+    }
+    /// Get the average over the taxicab radius at each point, edges are clamped.
+    pub fn blur(&self, radius: usize) -> TaxicabMap<f64> {
+        self.convolve(&Kernel::diamond(radius).normalized(), &Boundary::Clamp)
+    }
+}
+
+impl TaxicabMap<bool> {
+    /// Set all points within the taxicab radius of a `true` point, in `O(w·h)` by the distance transform.
+    pub fn dilate(&self, radius: usize) -> TaxicabMap<bool> {
+        self.distance_transform(|_, _, v| *v).map_values(|_, _, d| *d <= radius)
+    }
+    /// Keep only the points whose taxicab radius is all `true`, the edges do not erode.
+    pub fn erode(&self, radius: usize) -> TaxicabMap<bool> {
+        self.distance_transform(|_, _, v| !*v).map_values(|_, _, d| *d > radius)
+    }
+    /// Erode then dilate, removes features smaller than the radius.
+    pub fn open(&self, radius: usize) -> TaxicabMap<bool> {
+        self.erode(radius).dilate(radius)
+    }
+    /// Dilate then erode, fills gaps smaller than the radius.
+    pub fn close(&self, radius: usize) -> TaxicabMap<bool> {
+        self.dilate(radius).erode(radius)
+    }
+}
@@ -0,0 +1,7 @@
+use crate::TaxicabMap;
+
+pub mod critical;
+pub mod distance;
+pub mod influence;
+pub mod kernel;
+pub mod voronoi;
@@ -0,0 +1,75 @@
+use super::*;
+use crate::{Joint, path_finder::walk_neighbors};
+use ordered_float::OrderedFloat;
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+impl<T> TaxicabMap<T> {
+    /// Label each point with the index of the nearest seed by taxicab distance.
+    ///
+    /// Ties go to the seed with the lowest index, holes are left as `None`.
+    /// The distance is geometric, see [`TaxicabMap::taxicab_distance`].
+    pub fn voronoi(&self, seeds: &[(isize, isize)]) -> TaxicabMap<Option<usize>> {
+        self.map_values(|x, y, _| match self.has_point(x, y) {
+            true => {
+                seeds.iter().enumerate().min_by_key(|(i, seed)| (self.taxicab_distance((x, y), **seed), *i)).map(|(i, _)| i)
+            }
+            false => None,
+        })
+    }
+    /// Label each point with the index of the seed that reaches it at the lowest path cost.
+    ///
+    /// Ties go to the seed with the lowest index, unreachable points and holes are left as `None`.
+    /// Steps follow the topology and portals like [`PathFinder`](crate::PathFinder).
+    pub fn voronoi_path<P, C>(&self, seeds: &[(isize, isize)], passable: P, action_cost: C) -> TaxicabMap<Option<usize>>
+    where
+        P: Fn(isize, isize, &T) -> bool,
+        C: Fn(isize, isize, &T) -> f64,
+    {
+        let mut labels = self.map_values(|_, _, _| None);
+        let mut queue = BinaryHeap::new();
+        for (i, seed) in seeds.iter().enumerate() {
+            if let Some((x, y)) = self.wrap_point(seed.0, seed.1) {
+                if self.get_point(x, y).is_some_and(|v| passable(x, y, v)) {
+                    queue.push(Reverse((OrderedFloat(0.0), i, (x, y))));
+                }
+            }
+        }
+        while let Some(Reverse((cost, i, (x, y)))) = queue.pop() {
+            match labels.mut_point(x, y) {
+                Some(label @ None) => *label = Some(i),
+                _ => continue,
+            }
+            for (next, step) in walk_neighbors(self, &passable, &action_cost, (x, y)) {
+                if labels.get_point(next.0, next.1) == Some(&None) {
+                    queue.push(Reverse((cost + step, i, next)));
+                }
+            }
+        }
+        labels
+    }
+}
+
+impl TaxicabMap<Option<usize>> {
+    /// Get all joints that step from one labeled region into another.
+    ///
+    /// Each border is reported from both sides, so the joints leaving a region are those whose source has its label.
+    pub fn region_borders(&self) -> Vec<Joint> {
+        let mut out = vec![];
+        for (x, y, label) in self.points_all() {
+            let label = match label {
+                Some(s) => *s,
+                None => continue,
+            };
+            for joint in self.joints_nearby(x, y) {
+                let (tx, ty) = match self.joint_target(joint) {
+                    Some(target) => target.source(),
+                    None => continue,
+                };
+                if matches!(self.get_point(tx, ty), Some(Some(other)) if *other != label) {
+                    out.push(joint)
+                }
+            }
+        }
+        out
+    }
+}
@@ -1,54 +1,52 @@
 use super::*;
+use crate::path_finder::{PointFunction, walk_neighbors};
+use pathfinding::prelude::dijkstra_reach;
 use std::vec::IntoIter;
 
+/// Find all points reachable from the start within the action points, portals are followed.
 pub struct ActionFieldSolver<'a, T> {
     map: &'a TaxicabMap<T>,
-    open: BTreeMap<Point, f64>,
-    close: BTreeMap<Point, f64>,
-    passable: Box<dyn Fn(&Point, &T) -> bool>,
-    action_cost: Box<dyn Fn(&Point, &T) -> f64>,
+    start: (isize, isize),
+    passable: PointFunction<T, bool>,
+    action_cost: PointFunction<T, f64>,
     action_points: f64,
 }
 
 impl<T> TaxicabMap<T> {
-    /// Set the passable function.
-    ///
-    /// # Arguments
-    ///
-    /// * `passable`:  A function that returns true if the direction is passable.
-    ///
-    /// returns: PathFinder<T>
+    /// Create an action field solver, use `f64::INFINITY` as action points to get the whole distance field.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use hexagon_map::HexagonMap;
+    /// # use taxicab_map::TaxicabMap;
+    /// let map = TaxicabMap::square(5, &1.0);
+    /// let field = map.action_field((0, 0), 2.0).with_action_cost(|_, _, v| *v).solve();
+    /// assert_eq!(field.len(), 6);
     /// ```
-    pub fn action_field(&self, start: Point, action: f64) -> ActionFieldSolver<T> {
-        let mut open = BTreeMap::new();
-        open.insert(start, 0.0);
+    pub fn action_field(&self, start: (isize, isize), action: f64) -> ActionFieldSolver<'_, T> {
         ActionFieldSolver {
             map: self,
-            open,
-            close: Default::default(),
+            start,
             action_points: action,
-            passable: Box::new(|_, _| true),
-            action_cost: Box::new(|_, _| 0.0),
+            passable: Box::new(|_, _, _| true),
+            action_cost: Box::new(|_, _, _| 1.0),
         }
     }
 }
 
 impl<'a, T> ActionFieldSolver<'a, T> {
+    /// Set the passable function.
     pub fn with_passable<F>(mut self, passable: F) -> Self
     where
-        F: Fn(&Point, &T) -> bool + 'static,
+        F: Fn(isize, isize, &T) -> bool + 'static,
     {
         self.passable = Box::new(passable);
         self
     }
-    pub fn with_cost<F>(mut self, cost: F) -> Self
+    /// Set the action cost function.
+    pub fn with_action_cost<F>(mut self, cost: F) -> Self
     where
-        F: Fn(&Point, &T) -> f64 + 'static,
+        F: Fn(isize, isize, &T) -> f64 + 'static,
     {
         self.action_cost = Box::new(cost);
         self
@@ -56,45 +54,23 @@ impl<'a, T> ActionFieldSolver<'a, T> {
 }
 
 impl<'a, T> ActionFieldSolver<'a, T> {
-    /// Get all passable neighbors from a direction
-    pub fn neighbors(&self, point: &Point) -> Vec<(Point, f64)> {
-        let mut neighbors = Vec::with_capacity(6);
-        for direction in Direction::all() {
-            let key = point.go(direction);
-            if let Some(value) = self.map.get_point(key) {
-                if !(self.passable)(&key, value) {
-                    continue;
-                }
-                if self.close.contains_key(&key) {
-                    continue;
-                }
-                let cost = (self.action_cost)(point, value);
-                neighbors.push((key, cost));
-            }
-        }
-        neighbors
-    }
-    pub fn solve(mut self) -> impl Iterator<Item = (f64, Point)> {
-        while let Some((point, cost)) = self.open.pop_first() {
-            for (neighbor, neighbor_cost) in self.neighbors(&point) {
-                let new_cost = cost + neighbor_cost;
-                if new_cost > self.action_points {
-                    continue;
-                }
-                else {
-                    self.open.insert(neighbor, new_cost);
-                }
-            }
-            self.close.insert(point, cost);
-        }
-        self.close.iter().map(|(k, v)| (*v, *k)).sorted_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+    /// Get all reachable points and the minimum cost, sorted by the cost.
+    pub fn solve(self) -> Vec<(f64, (isize, isize))> {
+        let start = match self.map.wrap_point(self.start.0, self.start.1) {
+            Some(s) => s,
+            None => return vec![],
+        };
+        dijkstra_reach(&start, |p| walk_neighbors(self.map, &self.passable, &self.action_cost, *p))
+            .take_while(|item| item.total_cost.0 <= self.action_points)
+            .map(|item| (item.total_cost.0, item.node))
+            .collect()
     }
 }
 
 impl<'a, T> IntoIterator for ActionFieldSolver<'a, T> {
-    type Item = (f64, Point);
+    type Item = (f64, (isize, isize));
     type IntoIter = IntoIter<Self::Item>;
     fn into_iter(self) -> Self::IntoIter {
-        self.solve().collect_vec().into_iter()
+        self.solve().into_iter()
     }
 }
@@ -0,0 +1,108 @@
+use super::*;
+use crate::Neighborhood;
+use ndarray::Zip;
+
+/// Double buffered cellular automaton over a taxicab map.
+#[derive(Clone, Debug)]
+pub struct Automaton<T> {
+    current: TaxicabMap<T>,
+    next: TaxicabMap<T>,
+    offsets: Vec<(isize, isize)>,
+    generation: usize,
+}
+
+impl<T: Clone> Automaton<T> {
+    /// Create an automaton, the rule receives the neighbors in the order of [`Neighborhood::offsets`].
+    pub fn new(map: TaxicabMap<T>, neighborhood: Neighborhood) -> Self {
+        Self { next: map.clone(), current: map, offsets: neighborhood.offsets(), generation: 0 }
+    }
+    /// Get the current generation of the map
+    pub fn get_map(&self) -> &TaxicabMap<T> {
+        &self.current
+    }
+    /// Get the mutable current generation of the map
+    pub fn mut_map(&mut self) -> &mut TaxicabMap<T> {
+        &mut self.current
+    }
+    /// Take the current generation of the map
+    pub fn into_map(self) -> TaxicabMap<T> {
+        self.current
+    }
+    /// Get the number of steps made
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+    /// Compute the next generation into the back buffer and swap, holes are kept unchanged.
+    ///
+    /// The rule receives the point, the value and the neighbors, `None` for neighbors out of the map.
+    /// Neighbors across seams follow the cycles and twists of the map.
+    pub fn step<F>(&mut self, rule: F)
+    where
+        F: Fn(isize, isize, &T, &[Option<&T>]) -> T,
+    {
+        self.prepare();
+        let (current, offsets) = (&self.current, &self.offsets);
+        let mut around = Vec::with_capacity(offsets.len());
+        Zip::indexed(&mut self.next.dense).for_each(|(i, j), v| {
+            if let Some(new) = apply_rule(current, offsets, &mut around, i, j, &rule) {
+                *v = new
+            }
+        });
+        self.swap();
+    }
+    /// Compute the next generation in parallel, see [`Automaton::step`].
+    #[cfg(feature = "rayon")]
+    pub fn step_parallel<F>(&mut self, rule: F)
+    where
+        T: Send + Sync,
+        F: Fn(isize, isize, &T, &[Option<&T>]) -> T + Sync,
+    {
+        self.prepare();
+        use ndarray::parallel::prelude::{IntoParallelIterator, ParallelIterator};
+        let (current, offsets) = (&self.current, &self.offsets);
+        // one neighbor buffer for each split of the work
+        Zip::indexed(&mut self.next.dense).into_par_iter().for_each_init(
+            || Vec::with_capacity(offsets.len()),
+            |around, ((i, j), v)| {
+                if let Some(new) = apply_rule(current, offsets, around, i, j, &rule) {
+                    *v = new
+                }
+            },
+        );
+        self.swap();
+    }
+    /// Make the back buffer match the current map, in case it was edited.
+    fn prepare(&mut self) {
+        if self.next.dense.dim() == self.current.dense.dim() {
+            self.next.copy_geometry(&self.current);
+        }
+        else {
+            self.next = self.current.clone();
+        }
+    }
+    fn swap(&mut self) {
+        swap(&mut self.current, &mut self.next);
+        self.generation += 1;
+    }
+}
+
+/// Apply the rule to a point, the neighbors are collected into the reused buffer.
+fn apply_rule<'a, T, F>(
+    map: &'a TaxicabMap<T>,
+    offsets: &[(isize, isize)],
+    around: &mut Vec<Option<&'a T>>,
+    i: usize,
+    j: usize,
+    rule: &F,
+) -> Option<T>
+where
+    F: Fn(isize, isize, &T, &[Option<&T>]) -> T,
+{
+    if !map.has_relative(i, j) {
+        return None;
+    }
+    let (x, y) = relative_to_absolute(i, j, map.origin_x, map.origin_y);
+    around.clear();
+    around.extend(offsets.iter().map(|(dx, dy)| map.get_point(x + dx, y + dy)));
+    Some(rule(x, y, &map.dense[[i, j]], around))
+}
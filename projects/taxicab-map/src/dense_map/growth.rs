@@ -0,0 +1,134 @@
+use super::*;
+use std::{
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+};
+
+/// How the map grows when writing to a point out of bounds.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum GrowthPolicy {
+    /// Grow exactly enough to include the point.
+    Exact,
+    /// Grow at least the current size of the axis, so the size doubles.
+    Double,
+    /// Grow by multiples of the chunk size.
+    Chunk(usize),
+}
+
+impl GrowthPolicy {
+    fn amount(&self, need: usize, current: usize) -> usize {
+        match *self {
+            GrowthPolicy::Exact => need,
+            GrowthPolicy::Double => need.max(current.max(1)),
+            GrowthPolicy::Chunk(0) => need,
+            GrowthPolicy::Chunk(chunk) => need.div_ceil(chunk) * chunk,
+        }
+    }
+}
+
+/// The policy, the fill value and its clone function.
+type Setting<T> = (GrowthPolicy, T, fn(&T) -> T);
+
+/// The growth policy and fill value of a map, a setting that is not part of the identity of the map.
+///
+/// The fill value is kept with its clone function, so maps of values that are not `Clone` can still write points.
+#[derive(Clone)]
+pub(crate) struct Growth<T> {
+    setting: Option<Setting<T>>,
+}
+
+impl<T> Debug for Growth<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Growth").field(&self.setting.as_ref().map(|(policy, ..)| policy)).finish()
+    }
+}
+
+impl<T> Default for Growth<T> {
+    fn default() -> Self {
+        Self { setting: None }
+    }
+}
+
+impl<T> PartialEq for Growth<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Growth<T> {}
+
+impl<T> Hash for Growth<T> {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl<T> TaxicabMap<T> {
+    /// Get the growth policy of the map
+    pub fn get_growth(&self) -> Option<GrowthPolicy> {
+        self.growth.setting.as_ref().map(|(policy, ..)| *policy)
+    }
+    /// Set the growth policy of the map, new cells are filled with the given value
+    ///
+    /// Once set, [`TaxicabMap::set_point`] and [`TaxicabMap::mut_point_or_insert`] grow the map to reach points out of bounds.
+    pub fn set_growth(&mut self, policy: GrowthPolicy, fill: T)
+    where
+        T: Clone,
+    {
+        self.growth.setting = Some((policy, fill, T::clone));
+    }
+    /// Set the growth policy of the map, new cells are filled with the given value
+    pub fn with_growth(mut self, policy: GrowthPolicy, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        self.set_growth(policy, fill);
+        self
+    }
+    /// Disable the growth of the map, writes out of bounds will fail
+    pub fn clear_growth(&mut self) {
+        self.growth.setting = None;
+    }
+    /// Get the mutable reference of a point, grow the map if the point is out of bounds and a growth policy is set.
+    pub fn mut_point_or_insert(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        if !self.has_point(x, y) {
+            self.grow_to_include(x, y);
+        }
+        self.mut_point(x, y)
+    }
+    /// Grow the map by the growth policy until the point is included, cyclic axes never grow.
+    ///
+    /// Returns false if no growth policy is set.
+    pub fn grow_to_include(&mut self, x: isize, y: isize) -> bool {
+        let (policy, fill) = match self.growth.setting.take() {
+            Some(setting) => (setting.0, setting),
+            None => return false,
+        };
+        let (w, h) = self.get_size();
+        let (range_x, range_y) = self.get_range();
+        let (left, right) = match self.cycle_x {
+            true => (0, 0),
+            false => grow_amount(policy, x, range_x, w),
+        };
+        let (down, up) = match self.cycle_y {
+            true => (0, 0),
+            false => grow_amount(policy, y, range_y, h),
+        };
+        if left + right + down + up != 0 {
+            let (_, value, clone) = &fill;
+            self.reshape_with(w + left + right, h + down + up, left as isize, down as isize, &|| clone(value));
+        }
+        self.growth.setting = Some(fill);
+        true
+    }
+}
+
+fn grow_amount(policy: GrowthPolicy, value: isize, range: Range<isize>, current: usize) -> (usize, usize) {
+    if value < range.start {
+        (policy.amount(range.start.abs_diff(value), current), 0)
+    }
+    else if value >= range.end {
+        (0, policy.amount(value.abs_diff(range.end) + 1, current))
+    }
+    else {
+        (0, 0)
+    }
+}
@@ -23,27 +23,27 @@ pub struct MutGetTaxicabPoints<'i, T> {
 impl<'i, T> Iterator for GetTaxicabPoints<'i, T> {
     type Item = (isize, isize, &'i T);
     fn next(&mut self) -> Option<Self::Item> {
-        let (i, j) = self.cartesian.next()?;
+        let (i, j) = self.cartesian.find(|(i, j)| self.map.has_relative(*i, *j))?;
         let (x, y) = relative_to_absolute(i, j, self.map.origin_x, self.map.origin_y);
         let v = self.map.dense.get((i, j))?;
         Some((x, y, v))
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.cartesian.size_hint()
+        (0, self.cartesian.size_hint().1)
     }
 }
 
 impl<'i, T> Iterator for MutGetTaxicabPoints<'i, T> {
     type Item = (isize, isize, &'i mut T);
     fn next(&mut self) -> Option<Self::Item> {
-        let (i, j) = self.cartesian.next()?;
+        let (i, j) = self.cartesian.find(|(i, j)| self.map.has_relative(*i, *j))?;
         let (x, y) = relative_to_absolute(i, j, self.map.origin_x, self.map.origin_y);
-        // SAFETY:
+        // SAFETY: each cell is visited only once, so the mutable references never alias
         let v = unsafe { &mut *self.map.dense.get_mut_ptr((i, j))? };
         Some((x, y, v))
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.cartesian.size_hint()
+        (0, self.cartesian.size_hint().1)
     }
 }
 
@@ -54,32 +54,26 @@ impl<T> TaxicabMap<T> {
         GetTaxicabPoints { map: self, cartesian: (0..w).cartesian_product(0..h) }
     }
     /// Get an iterator over all points in the map.
-    pub fn points_mut(&mut self) -> MutGetTaxicabPoints<T> {
+    pub fn points_mut(&mut self) -> MutGetTaxicabPoints<'_, T> {
         let (w, h) = self.get_size();
         MutGetTaxicabPoints { map: self, cartesian: (0..w).cartesian_product(0..h) }
     }
 }
 
+/// Same order as the diamond points with distance 1.
+const NEARBY: [Direction; 4] = [Direction::X(true), Direction::Y(true), Direction::X(false), Direction::Y(false)];
+
 /// A diamond shaped area around a point.
-pub struct GetTaxicabPointsAround {
+pub struct GetTaxicabPointsAround<'i, T> {
+    map: &'i TaxicabMap<T>,
     points: DiamondPoints,
-    origin_x: isize,
-    origin_y: isize,
-    w: isize,
-    h: isize,
-    cycle_x: bool,
-    cycle_y: bool,
 }
 
-impl Iterator for GetTaxicabPointsAround {
+impl<'i, T> Iterator for GetTaxicabPointsAround<'i, T> {
     type Item = (isize, isize);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (x, y) = self.points.next()?;
-        match absolute_to_relative(x, y, self.origin_x, self.origin_y, self.w, self.h, self.cycle_x, self.cycle_y) {
-            Some(_) => Some((x, y)),
-            None => self.next(),
-        }
+        self.points.find(|(x, y)| self.map.has_point(*x, *y))
     }
 }
 
@@ -144,26 +138,22 @@ impl Iterator for DiamondPoints {
 }
 
 impl<T> TaxicabMap<T> {
-    /// Find at most 4 points that are exists and adjacent to a direction.
-    pub fn points_nearby(&self, x: isize, y: isize) -> impl Iterator<Item = (isize, isize)> {
-        self.points_around(x, y, 1)
+    /// Find at most 4 points that are exists and adjacent to a direction, portals are followed.
+    pub fn points_nearby(&self, x: isize, y: isize) -> impl Iterator<Item = (isize, isize)> + '_ {
+        NEARBY.into_iter().filter_map(move |direction| self.joint_neighbor(Joint::new(x, y, direction)))
     }
-    /// Find at most 4 joints that are exists and adjacent to a direction.
-    pub fn joints_nearby(&self, x: isize, y: isize) -> impl Iterator<Item = Joint> {
-        self.points_around(x, y, 1).map(move |(tx, ty)| Joint::from_point((x, y), (tx, ty)))
+    /// Find at most 4 joints that are exists and adjacent to a direction, portals are followed.
+    pub fn joints_nearby(&self, x: isize, y: isize) -> impl Iterator<Item = Joint> + '_ {
+        NEARBY
+            .into_iter()
+            .map(move |direction| Joint::new(x, y, direction))
+            .filter(|joint| self.joint_neighbor(*joint).is_some())
     }
     /// Find all points that are within a certain distance of a direction.
-    pub fn points_around(&self, x: isize, y: isize, steps: usize) -> GetTaxicabPointsAround {
-        let (w, h) = self.get_isize();
-        GetTaxicabPointsAround {
-            points: DiamondPoints::new(x, y, steps as isize),
-            origin_x: self.origin_x,
-            origin_y: self.origin_y,
-            w,
-            h,
-            cycle_x: self.cycle_x,
-            cycle_y: self.cycle_y,
-        }
+    ///
+    /// Points across seams are kept unwrapped, use [`TaxicabMap::wrap_point`] to get the coordinates inside the bounds.
+    pub fn points_around(&self, x: isize, y: isize, steps: usize) -> GetTaxicabPointsAround<'_, T> {
+        GetTaxicabPointsAround { map: self, points: DiamondPoints::new(x, y, steps as isize) }
     }
 }
 
@@ -178,30 +168,27 @@ impl<'i, T> Iterator for GetTaxicabLine<'i, T> {
     type Item = Vec<(isize, isize, &'i T)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (w, h) = self.map.get_isize();
-        let mut out = Vec::new();
-        match self.direction {
-            Direction::Y(rev) if self.line < h => {
-                let y = match rev {
-                    true => self.line,
-                    false => h - self.line - 1,
-                };
-                for x in 0..w {
-                    let v = self.map.get_point(x, y)?;
-                    out.push((x, y, v));
-                }
+        let (w, h) = self.map.dense.dim();
+        let line = self.line as usize;
+        let cells: Vec<(usize, usize)> = match self.direction {
+            Direction::Y(rev) if line < h => {
+                let j = if rev { line } else { h - line - 1 };
+                (0..w).map(|i| (i, j)).collect()
             }
-            Direction::X(rev) if self.line < w => {
-                let x = match rev {
-                    true => self.line,
-                    false => w - self.line - 1,
-                };
-                for y in 0..h {
-                    let v = self.map.get_point(x, y)?;
-                    out.push((x, y, v));
-                }
+            Direction::X(rev) if line < w => {
+                let i = if rev { line } else { w - line - 1 };
+                (0..h).map(|j| (i, j)).collect()
             }
             _ => return None,
+        };
+        let mut out = Vec::with_capacity(cells.len());
+        for (i, j) in cells {
+            // holes are skipped, the line goes on
+            if !self.map.has_relative(i, j) {
+                continue;
+            }
+            let (x, y) = relative_to_absolute(i, j, self.map.origin_x, self.map.origin_y);
+            out.push((x, y, &self.map.dense[[i, j]]));
         }
         self.line += 1;
         Some(out)
@@ -0,0 +1,91 @@
+use super::*;
+use std::{
+    collections::BTreeSet,
+    hash::{Hash, Hasher},
+};
+
+type Bounds = (Range<isize>, Range<isize>);
+
+/// Points touched through mutable access since the journal was started, not part of the identity of the map.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Journal {
+    recording: Option<Recording>,
+}
+
+#[derive(Clone, Debug)]
+struct Recording {
+    points: BTreeSet<(isize, isize)>,
+    /// Bounds at the start, moved along with the values
+    bounds: Bounds,
+    origin: (isize, isize),
+    /// How far the values moved with the origin since the start
+    moved: (isize, isize),
+}
+
+/// What the journal recorded, see [`TaxicabMap::journal_diff`].
+pub(crate) struct JournalEntries {
+    /// Touched points, in the coordinates after moving the origin
+    pub points: BTreeSet<(isize, isize)>,
+    /// Bounds at the start, in the coordinates after moving the origin
+    pub bounds: Bounds,
+    /// The origin that moves the values of the map at the start to their current place, if they moved
+    pub origin: Option<(isize, isize)>,
+}
+
+impl PartialEq for Journal {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Journal {}
+
+impl Hash for Journal {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl<T> TaxicabMap<T> {
+    /// Start recording the points touched by [`TaxicabMap::set_point`], [`TaxicabMap::mut_point`] and `IndexMut`,
+    /// and the moves of [`TaxicabMap::set_origin`] and [`TaxicabMap::shift_origin`].
+    ///
+    /// Changes through [`TaxicabMap::points_mut`] are not recorded. Restarting clears the journal, see [`TaxicabMap::journal_diff`].
+    pub fn start_journal(&mut self) {
+        self.journal.recording = Some(self.new_recording());
+    }
+    /// Stop recording and drop the journal.
+    pub fn stop_journal(&mut self) {
+        self.journal.recording = None;
+    }
+    /// Check if the map is recording a journal.
+    pub fn is_journaling(&self) -> bool {
+        self.journal.recording.is_some()
+    }
+    fn new_recording(&self) -> Recording {
+        Recording { points: BTreeSet::new(), bounds: self.get_range(), origin: self.get_origin(), moved: (0, 0) }
+    }
+    /// Take what was recorded, the journal keeps recording from now.
+    pub(crate) fn take_journal(&mut self) -> Option<JournalEntries> {
+        let fresh = self.new_recording();
+        let taken = std::mem::replace(self.journal.recording.as_mut()?, fresh);
+        let origin = match taken.moved {
+            (0, 0) => None,
+            (dx, dy) => Some((taken.origin.0 + dx, taken.origin.1 + dy)),
+        };
+        Some(JournalEntries { points: taken.points, bounds: taken.bounds, origin })
+    }
+    pub(crate) fn record(&mut self, i: usize, j: usize) {
+        if let Some(recording) = &mut self.journal.recording {
+            recording.points.insert(relative_to_absolute(i, j, self.origin_x, self.origin_y));
+        }
+    }
+    /// The values move with the origin, so do the touched points and the bounds at the start.
+    pub(crate) fn record_move(&mut self, dx: isize, dy: isize) {
+        if let Some(recording) = &mut self.journal.recording {
+            recording.points = recording.points.iter().map(|(x, y)| (x + dx, y + dy)).collect();
+            let (x, y) = &mut recording.bounds;
+            *x = x.start + dx..x.end + dx;
+            *y = y.start + dy..y.end + dy;
+            recording.moved = (recording.moved.0 + dx, recording.moved.1 + dy);
+        }
+    }
+}
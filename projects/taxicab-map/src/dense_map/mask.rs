@@ -0,0 +1,50 @@
+use super::*;
+
+impl<T: Clone> TaxicabMap<T> {
+    /// Create a diamond shaped taxicab map centered at the origin, fill with cloneable value
+    pub fn diamond(radius: usize, fill: &T) -> Self {
+        let r = radius as isize;
+        let mut mask = TaxicabMap::square(2 * radius + 1, &false).with_origin(-r, -r);
+        for (x, y) in (-r..=r).cartesian_product(-r..=r) {
+            mask[(x, y)] = x.abs() + y.abs() <= r;
+        }
+        Self::from_mask(&mask, fill)
+    }
+    /// Create a taxicab map with the same shape, origin and cycle as the mask, only `true` points exist.
+    pub fn from_mask(mask: &TaxicabMap<bool>, fill: &T) -> Self {
+        let dense = Array2::from_shape_fn(mask.get_size(), |_| fill.clone());
+        let mut out = mask.with_dense(dense);
+        out.mask = Some(Array2::from_shape_fn(mask.get_size(), |(i, j)| mask.has_relative(i, j) && mask.dense[[i, j]]));
+        out
+    }
+}
+
+impl<T> TaxicabMap<T> {
+    /// Check if the map has points masked out
+    pub fn is_masked(&self) -> bool {
+        self.mask.is_some()
+    }
+    /// Mark a point in the bounds as existing or not, returns false if the point is out of bounds.
+    pub fn set_point_exists(&mut self, x: isize, y: isize, exists: bool) -> bool {
+        let ((i, j), _) = match self.wrap_relative(x, y) {
+            Some(s) => s,
+            None => return false,
+        };
+        let dim = self.dense.dim();
+        self.mask.get_or_insert_with(|| Array2::from_elem(dim, true))[[i, j]] = exists;
+        if !exists {
+            self.retain_portals();
+        }
+        true
+    }
+    /// Get the existence of all points in the bounds as a map.
+    pub fn get_mask(&self) -> TaxicabMap<bool> {
+        let mut out = self.with_dense(Array2::from_shape_fn(self.dense.dim(), |(i, j)| self.has_relative(i, j)));
+        out.mask = None;
+        out
+    }
+    /// Remove the mask, all points in the bounds exist again.
+    pub fn clear_mask(&mut self) {
+        self.mask = None;
+    }
+}
@@ -1,16 +1,26 @@
 use crate::{Direction, Joint};
 use itertools::{Itertools, Product};
-use ndarray::Array2;
+use ndarray::{Array2, s};
+use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     mem::swap,
     ops::{Index, IndexMut, Range},
 };
 
-// pub mod action_field;
+pub mod action_field;
+pub mod automaton;
 // pub mod path_finder;
+pub use self::{growth::GrowthPolicy, portal::Portal, topology::Topology};
+mod growth;
 mod indexes;
 pub mod iters;
+mod journal;
+mod mask;
+pub(crate) mod pairs;
+mod portal;
+mod topology;
 
 /// A dense manhattan map, if your map size will grow, or most areas will be blank, this is a better choice.
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -18,8 +28,20 @@ pub struct TaxicabMap<T> {
     dense: Array2<T>,
     cycle_x: bool,
     cycle_y: bool,
+    #[serde(default)]
+    twist_x: bool,
+    #[serde(default)]
+    twist_y: bool,
     origin_x: isize,
     origin_y: isize,
+    #[serde(skip, default = "growth::Growth::default")]
+    growth: growth::Growth<T>,
+    #[serde(default)]
+    mask: Option<Array2<bool>>,
+    #[serde(default, with = "pairs")]
+    portals: BTreeMap<Joint, Portal>,
+    #[serde(skip)]
+    journal: journal::Journal,
 }
 
 impl<T: Clone> TaxicabMap<T> {
@@ -30,39 +52,143 @@ impl<T: Clone> TaxicabMap<T> {
     /// Create a rectangle taxicab map, fill with cloneable value
     pub fn rectangle(width: usize, height: usize, fill: &T) -> Self {
         let dense = Array2::from_shape_fn((width, height), |_| fill.clone());
-        Self { dense, cycle_x: false, cycle_y: false, origin_x: 0, origin_y: 0 }
+        Self {
+            dense,
+            cycle_x: false,
+            cycle_y: false,
+            twist_x: false,
+            twist_y: false,
+            origin_x: 0,
+            origin_y: 0,
+            growth: Default::default(),
+            mask: None,
+            portals: BTreeMap::new(),
+            journal: Default::default(),
+        }
     }
     /// Extend the map in a direction, fill with cloneable value
+    ///
+    /// `X(true)` and `Y(true)` grow the low side, `X(false)` and `Y(false)` grow the high side.
+    /// Growing the low side moves the origin, so the absolute coordinates of existing cells are kept.
     pub fn extend(&mut self, direction: Direction, size: usize, fill: &T) {
-        let (x, y) = self.dense.dim();
-        let (w, h) = match direction {
-            Direction::X(_) => (x + size, y),
-            Direction::Y(_) => (x, y + size),
-        };
-        let mut new = Array2::from_shape_fn((w, h), |_| fill.clone());
+        let (w, h) = self.get_size();
+        let offset = size as isize;
         match direction {
-            Direction::X(true) => {
-                for (x, y) in (0..x).cartesian_product(0..y) {
-                    swap(&mut new[[x + size, y]], &mut self.dense[[x, y]]);
-                }
-            }
-            Direction::X(false) => {
-                for (x, y) in (0..x).cartesian_product(0..y) {
-                    swap(&mut new[[x, y]], &mut self.dense[[x, y]]);
-                }
-            }
-            Direction::Y(true) => {
-                for (x, y) in (0..x).cartesian_product(0..y) {
-                    swap(&mut new[[x, y + size]], &mut self.dense[[x, y]]);
-                }
-            }
-            Direction::Y(false) => {
-                for (x, y) in (0..x).cartesian_product(0..y) {
-                    swap(&mut new[[x, y]], &mut self.dense[[x, y]]);
+            Direction::X(true) => self.reshape(w + size, h, offset, 0, fill),
+            Direction::X(false) => self.reshape(w + size, h, 0, 0, fill),
+            Direction::Y(true) => self.reshape(w, h + size, 0, offset, fill),
+            Direction::Y(false) => self.reshape(w, h + size, 0, 0, fill),
+        }
+    }
+    /// Remove rows or columns from one side of the map, the absolute coordinates of retained cells are kept.
+    ///
+    /// The sides match [`TaxicabMap::extend`], so shrinking in the same direction undoes an extend.
+    pub fn shrink(&mut self, direction: Direction, size: usize) {
+        let (w, h) = self.get_size();
+        match direction {
+            Direction::X(true) => self.crop_relative(size.min(w)..w, 0..h),
+            Direction::X(false) => self.crop_relative(0..w.saturating_sub(size), 0..h),
+            Direction::Y(true) => self.crop_relative(0..w, size.min(h)..h),
+            Direction::Y(false) => self.crop_relative(0..w, 0..h.saturating_sub(size)),
+        }
+    }
+    /// Crop the map to a rectangle in absolute coordinates, the parts outside the map are ignored.
+    pub fn crop(&mut self, x: Range<isize>, y: Range<isize>) {
+        let (w, h) = self.get_isize();
+        let clamp = |v: isize, origin: isize, max: isize| (v - origin).clamp(0, max) as usize;
+        let x = clamp(x.start, self.origin_x, w)..clamp(x.end, self.origin_x, w);
+        let y = clamp(y.start, self.origin_y, h)..clamp(y.end, self.origin_y, h);
+        self.crop_relative(x, y)
+    }
+    /// Resize the map to the given size, the anchor decides which part of the map stays in place.
+    pub fn resize(&mut self, width: usize, height: usize, anchor_x: Anchor, anchor_y: Anchor, fill: &T) {
+        let (w, h) = self.get_isize();
+        let dx = anchor_x.offset(w, width as isize);
+        let dy = anchor_y.offset(h, height as isize);
+        self.reshape(width, height, dx, dy, fill)
+    }
+    /// Set the bounds of the map to a rectangle in absolute coordinates, points in both keep their values.
+    pub fn reframe(&mut self, x: Range<isize>, y: Range<isize>, fill: &T) {
+        let (dx, dy) = (self.origin_x - x.start, self.origin_y - y.start);
+        self.reshape(x.len(), y.len(), dx, dy, fill)
+    }
+    fn crop_relative(&mut self, x: Range<usize>, y: Range<usize>) {
+        let (x, y) = (x.start..x.end.max(x.start), y.start..y.end.max(y.start));
+        self.dense = self.dense.slice(s![x.clone(), y.clone()]).to_owned();
+        if let Some(mask) = &mut self.mask {
+            *mask = mask.slice(s![x.clone(), y.clone()]).to_owned();
+        }
+        self.offset_origin(x.start as isize, y.start as isize);
+        self.retain_portals();
+    }
+    fn reshape(&mut self, width: usize, height: usize, dx: isize, dy: isize, fill: &T) {
+        self.reshape_with(width, height, dx, dy, &|| fill.clone())
+    }
+}
+
+impl<T> TaxicabMap<T> {
+    /// Move cell `(i, j)` to `(i + dx, j + dy)` of a new array, cells out of the new array are dropped.
+    fn reshape_with(&mut self, width: usize, height: usize, dx: isize, dy: isize, fill: &dyn Fn() -> T) {
+        let mut new = Array2::from_shape_fn((width, height), |_| fill());
+        let mut new_mask = self.mask.as_ref().map(|_| Array2::from_elem((width, height), true));
+        let (w, h) = self.get_isize();
+        for (x, y) in (0..w).cartesian_product(0..h) {
+            let (i, j) = (x + dx, y + dy);
+            if i >= 0 && j >= 0 && i < width as isize && j < height as isize {
+                let (i, j, x, y) = (i as usize, j as usize, x as usize, y as usize);
+                swap(&mut new[[i, j]], &mut self.dense[[x, y]]);
+                if let (Some(new_mask), Some(mask)) = (&mut new_mask, &self.mask) {
+                    new_mask[[i, j]] = mask[[x, y]];
                 }
             }
         }
         self.dense = new;
+        self.mask = new_mask;
+        self.offset_origin(-dx, -dy);
+        self.retain_portals();
+    }
+}
+
+impl<T: Clone + PartialEq> TaxicabMap<T> {
+    /// Crop the map to the bounding box of all cells that are not equal to the blank value.
+    ///
+    /// If all cells are blank, the map will be empty.
+    pub fn shrink_to_fit(&mut self, blank: &T) {
+        let mut bound: Option<(Range<usize>, Range<usize>)> = None;
+        for ((i, j), v) in self.dense.indexed_iter() {
+            if v == blank || !self.has_relative(i, j) {
+                continue;
+            }
+            bound = match bound {
+                Some((x, y)) => Some((x.start.min(i)..x.end.max(i + 1), y.start.min(j)..y.end.max(j + 1))),
+                None => Some((i..i + 1, j..j + 1)),
+            };
+        }
+        match bound {
+            Some((x, y)) => self.crop_relative(x, y),
+            None => self.crop_relative(0..0, 0..0),
+        }
+    }
+}
+
+/// Which part of the map stays in place when resizing.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Anchor {
+    /// Keep the cells with the smallest coordinates, grow or shrink at the positive side.
+    Start,
+    /// Keep the cells in the middle, grow or shrink at both sides.
+    Center,
+    /// Keep the cells with the largest coordinates, grow or shrink at the negative side.
+    End,
+}
+
+impl Anchor {
+    pub(crate) fn offset(&self, old: isize, new: isize) -> isize {
+        match self {
+            Anchor::Start => 0,
+            Anchor::Center => (new - old) / 2,
+            Anchor::End => new - old,
+        }
     }
 }
 
@@ -86,50 +212,146 @@ impl<T> TaxicabMap<T> {
     pub fn get_origin(&self) -> (isize, isize) {
         (self.origin_x, self.origin_y)
     }
-    /// Set the origin of the map
+    /// Set the origin of the map, all values move with it
     pub fn set_origin(&mut self, x: isize, y: isize) {
-        self.origin_x = x;
-        self.origin_y = y;
+        self.shift_origin(x - self.origin_x, y - self.origin_y)
     }
     /// Set the origin of the map
     pub fn with_origin(mut self, x: isize, y: isize) -> Self {
         self.set_origin(x, y);
         self
     }
-    /// Shift the origin of the map
+    /// Shift the origin of the map, all values move with it
     pub fn shift_origin(&mut self, x: isize, y: isize) {
+        self.record_move(x, y);
+        self.shift_portals(x, y);
+        self.offset_origin(x, y);
+    }
+    /// Shift the origin without moving the values in the journal, for reshaping that keeps absolute coordinates.
+    fn offset_origin(&mut self, x: isize, y: isize) {
         self.origin_x += x;
         self.origin_y += y;
     }
-    /// Shift the origin of the map
+    /// Get the size of the map
     pub fn get_size(&self) -> (usize, usize) {
         self.dense.dim()
     }
+    /// Get the absolute range of the map
+    pub fn get_range(&self) -> (Range<isize>, Range<isize>) {
+        let (w, h) = self.get_isize();
+        (self.origin_x..self.origin_x + w, self.origin_y..self.origin_y + h)
+    }
     pub(crate) fn get_isize(&self) -> (isize, isize) {
         let (w, h) = self.dense.dim();
         (w as isize, h as isize)
     }
-    /// Get the range of the map
-    pub fn has_point(&self, x: isize, y: isize) -> bool {
+    pub(crate) fn get_relative(&self, x: isize, y: isize) -> Option<(usize, usize)> {
+        let ((i, j), _) = self.wrap_relative(x, y)?;
+        self.has_relative(i, j).then_some((i, j))
+    }
+    /// Wrap an absolute point into the bounds, also returns whether the x and y axes are mirrored by twisted seams.
+    pub(crate) fn wrap_relative(&self, x: isize, y: isize) -> Option<((usize, usize), (bool, bool))> {
         let (w, h) = self.get_isize();
-        absolute_to_relative(x, y, self.origin_x, self.origin_y, w, h, self.cycle_x, self.cycle_y).is_some()
+        let (mut x, mut y) = (x - self.origin_x, y - self.origin_y);
+        let (mut flip_x, mut flip_y) = (false, false);
+        if self.cycle_x && w > 0 {
+            flip_y = self.twist_x && x.div_euclid(w) % 2 != 0;
+            x = x.rem_euclid(w);
+        }
+        else if x < 0 || x >= w {
+            return None;
+        }
+        if flip_y {
+            y = h - 1 - y;
+        }
+        if self.cycle_y && h > 0 {
+            if self.twist_y && y.div_euclid(h) % 2 != 0 {
+                flip_x = true;
+                x = w - 1 - x;
+            }
+            y = y.rem_euclid(h);
+        }
+        else if y < 0 || y >= h {
+            return None;
+        }
+        Some(((x as usize, y as usize), (flip_x, flip_y)))
+    }
+    pub(crate) fn get_relative_value(&self, i: usize, j: usize) -> T
+    where
+        T: Copy,
+    {
+        self.dense[[i, j]]
+    }
+    pub(crate) fn set_relative_value(&mut self, i: usize, j: usize, value: T) {
+        self.dense[[i, j]] = value;
+    }
+    /// Create a map with the same geometry, values are mapped from the points, including the holes.
+    pub fn map_values<U, F>(&self, mut f: F) -> TaxicabMap<U>
+    where
+        F: FnMut(isize, isize, &T) -> U,
+    {
+        let dense = Array2::from_shape_fn(self.dense.dim(), |(i, j)| {
+            let (x, y) = relative_to_absolute(i, j, self.origin_x, self.origin_y);
+            f(x, y, &self.dense[[i, j]])
+        });
+        self.with_dense(dense)
+    }
+    /// Create a map with the same geometry from another dense array of the same shape.
+    pub(crate) fn with_dense<U>(&self, dense: Array2<U>) -> TaxicabMap<U> {
+        debug_assert_eq!(dense.dim(), self.dense.dim());
+        TaxicabMap {
+            dense,
+            cycle_x: self.cycle_x,
+            cycle_y: self.cycle_y,
+            twist_x: self.twist_x,
+            twist_y: self.twist_y,
+            origin_x: self.origin_x,
+            origin_y: self.origin_y,
+            growth: Default::default(),
+            mask: self.mask.clone(),
+            portals: self.portals.clone(),
+            journal: Default::default(),
+        }
+    }
+    /// Copy the origin, cycles, twists, mask and portals from another map of the same size.
+    pub(crate) fn copy_geometry<U>(&mut self, other: &TaxicabMap<U>) {
+        debug_assert_eq!(other.dense.dim(), self.dense.dim());
+        self.cycle_x = other.cycle_x;
+        self.cycle_y = other.cycle_y;
+        self.twist_x = other.twist_x;
+        self.twist_y = other.twist_y;
+        self.origin_x = other.origin_x;
+        self.origin_y = other.origin_y;
+        self.mask = other.mask.clone();
+        self.portals = other.portals.clone();
+    }
+    pub(crate) fn has_relative(&self, i: usize, j: usize) -> bool {
+        match &self.mask {
+            Some(mask) => mask[[i, j]],
+            None => true,
+        }
+    }
+    /// Check if the point exists in the map, masked out points do not exist
+    pub fn has_point(&self, x: isize, y: isize) -> bool {
+        self.get_relative(x, y).is_some()
     }
-    /// Get the range of the map
+    /// Get the value of a point, masked out points return `None`
     pub fn get_point(&self, x: isize, y: isize) -> Option<&T> {
-        let (w, h) = self.get_isize();
-        let (i, j) = absolute_to_relative(x, y, self.origin_x, self.origin_y, w, h, self.cycle_x, self.cycle_y)?;
+        let (i, j) = self.get_relative(x, y)?;
         // in fact (i, j) must be in range, could use get_unchecked
         self.dense.get((i, j))
     }
-    /// Get the range of the map
+    /// Get the mutable value of a point, masked out points return `None`
     pub fn mut_point(&mut self, x: isize, y: isize) -> Option<&mut T> {
-        let (w, h) = self.get_isize();
-        let (i, j) = absolute_to_relative(x, y, self.origin_x, self.origin_y, w, h, self.cycle_x, self.cycle_y)?;
+        let (i, j) = self.get_relative(x, y)?;
+        self.record(i, j);
         self.dense.get_mut((i, j))
     }
-    /// Get the range of the map
+    /// Set the value of a point, the map grows if a growth policy is set, see [`TaxicabMap::set_growth`]
+    ///
+    /// Returns false if the point does not exist and the map does not grow to it.
     pub fn set_point(&mut self, x: isize, y: isize, value: T) -> bool {
-        match self.mut_point(x, y) {
+        match self.mut_point_or_insert(x, y) {
             Some(v) => {
                 *v = value;
                 true
@@ -137,37 +359,17 @@ impl<T> TaxicabMap<T> {
             None => false,
         }
     }
+    /// Get any value stored in the map, holes included.
+    pub(crate) fn any_value(&self) -> Option<&T> {
+        self.dense.iter().next()
+    }
     /// Count all defined points in the map.
     pub fn count_points(&self) -> usize {
-        self.dense.len()
-    }
-}
-
-#[inline]
-pub(crate) fn absolute_to_relative(
-    x: isize,
-    y: isize,
-    origin_x: isize,
-    origin_y: isize,
-    w: isize,
-    h: isize,
-    cycle_x: bool,
-    cycle_y: bool,
-) -> Option<(usize, usize)> {
-    let (mut x, mut y) = (x - origin_x, y - origin_y);
-    if cycle_x {
-        x = x.rem_euclid(w);
-    }
-    else if x < 0 || x >= w {
-        return None;
-    }
-    if cycle_y {
-        y = y.rem_euclid(h);
-    }
-    else if y < 0 || y >= h {
-        return None;
+        match &self.mask {
+            Some(mask) => mask.iter().filter(|v| **v).count(),
+            None => self.dense.len(),
+        }
     }
-    Some((x as usize, y as usize))
 }
 
 #[inline]
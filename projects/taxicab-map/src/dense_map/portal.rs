@@ -0,0 +1,91 @@
+use super::*;
+
+/// A one-way link from a joint to an arbitrary point and direction.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Portal {
+    target: Joint,
+    cost: Option<OrderedFloat<f64>>,
+}
+
+impl Portal {
+    /// Create a portal that arrives at the source of the target joint, facing its direction.
+    pub fn new(target: Joint) -> Self {
+        Self { target, cost: None }
+    }
+    /// Set the cost of passing the portal, which replaces the cost of entering the target point.
+    pub fn with_cost(mut self, cost: f64) -> Self {
+        self.cost = Some(OrderedFloat(cost));
+        self
+    }
+    /// Get the arrival joint of the portal.
+    pub fn get_target(&self) -> Joint {
+        self.target
+    }
+    /// Get the cost of passing the portal.
+    pub fn get_cost(&self) -> Option<f64> {
+        self.cost.map(|v| v.0)
+    }
+}
+
+impl<T> TaxicabMap<T> {
+    /// Link the entrance joint to a portal, returns false if the entrance or the exit does not exist.
+    pub fn add_portal(&mut self, entrance: Joint, portal: Portal) -> bool {
+        let (entrance, target) = match (self.wrap_joint(entrance), self.wrap_joint(portal.target)) {
+            (Some(entrance), Some(target)) => (entrance, target),
+            _ => return false,
+        };
+        self.portals.insert(entrance, Portal { target, ..portal });
+        true
+    }
+    /// Remove the portal at the entrance joint.
+    pub fn remove_portal(&mut self, entrance: Joint) -> Option<Portal> {
+        let entrance = self.wrap_joint(entrance)?;
+        self.portals.remove(&entrance)
+    }
+    /// Get the portal at the entrance joint.
+    pub fn get_portal(&self, entrance: Joint) -> Option<&Portal> {
+        if self.portals.is_empty() {
+            return None;
+        }
+        self.portals.get(&self.wrap_joint(entrance)?)
+    }
+    /// Get all portals in the map.
+    pub fn portals(&self) -> impl Iterator<Item = (Joint, Portal)> + '_ {
+        self.portals.iter().map(|(k, v)| (*k, *v))
+    }
+    /// Remove all portals in the map.
+    pub fn clear_portals(&mut self) {
+        self.portals.clear();
+    }
+    /// Move all portals with the values, the entrances and exits keep their cells.
+    pub(crate) fn shift_portals(&mut self, dx: isize, dy: isize) {
+        if self.portals.is_empty() {
+            return;
+        }
+        let shift = |joint: Joint| Joint::new(joint.source().0 + dx, joint.source().1 + dy, joint.get_direction());
+        self.portals = std::mem::take(&mut self.portals)
+            .into_iter()
+            .map(|(entrance, portal)| (shift(entrance), Portal { target: shift(portal.target), ..portal }))
+            .collect();
+    }
+    /// Drop the portals whose entrance or exit is no longer a point of the map.
+    pub(crate) fn retain_portals(&mut self) {
+        if self.portals.is_empty() {
+            return;
+        }
+        let mut portals = std::mem::take(&mut self.portals);
+        let exists = |joint: Joint| self.wrap_point(joint.source().0, joint.source().1) == Some(joint.source());
+        portals.retain(|entrance, portal| exists(*entrance) && exists(portal.target));
+        self.portals = portals;
+    }
+    /// Get the point reached by walking through the joint, portals are followed.
+    pub(crate) fn joint_neighbor(&self, joint: Joint) -> Option<(isize, isize)> {
+        match self.get_portal(joint) {
+            Some(portal) => self.wrap_point(portal.target.source().0, portal.target.source().1),
+            None => {
+                let (x, y) = joint.target();
+                self.has_point(x, y).then_some((x, y))
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use super::*;
+
+/// The surface formed by gluing the edges of the map.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Topology {
+    /// No edges are glued.
+    Plane,
+    /// The left and right edges are glued.
+    Cylinder,
+    /// The left and right edges are glued, the top and bottom edges are glued.
+    Torus,
+    /// The left and right edges are glued with the y axis flipped.
+    Mobius,
+    /// The left and right edges are glued with the y axis flipped, the top and bottom edges are glued.
+    Klein,
+    /// The left and right edges are glued with the y axis flipped, the top and bottom edges are glued with the x axis flipped.
+    Projective,
+}
+
+impl Topology {
+    /// Get the `(cycle_x, cycle_y)` and `(twist_x, twist_y)` config of the topology.
+    pub fn get_config(&self) -> ((bool, bool), (bool, bool)) {
+        match self {
+            Topology::Plane => ((false, false), (false, false)),
+            Topology::Cylinder => ((true, false), (false, false)),
+            Topology::Torus => ((true, true), (false, false)),
+            Topology::Mobius => ((true, false), (true, false)),
+            Topology::Klein => ((true, true), (true, false)),
+            Topology::Projective => ((true, true), (true, true)),
+        }
+    }
+}
+
+impl<T> TaxicabMap<T> {
+    /// Get the twist config of the map, crossing a twisted x seam flips the y axis and vice versa.
+    pub fn get_twist(&self) -> (bool, bool) {
+        (self.twist_x, self.twist_y)
+    }
+    /// Set the twist config of the map, twists only take effect on cyclic axes.
+    pub fn set_twist(&mut self, twist_x: bool, twist_y: bool) {
+        self.twist_x = twist_x;
+        self.twist_y = twist_y;
+    }
+    /// Set the twist config of the map, twists only take effect on cyclic axes.
+    pub fn with_twist(mut self, twist_x: bool, twist_y: bool) -> Self {
+        self.set_twist(twist_x, twist_y);
+        self
+    }
+    /// Get the topology of the map
+    pub fn get_topology(&self) -> Topology {
+        let twist_x = self.cycle_x && self.twist_x;
+        let twist_y = self.cycle_y && self.twist_y;
+        match (self.cycle_x, self.cycle_y) {
+            (false, false) => Topology::Plane,
+            (true, true) if twist_x && twist_y => Topology::Projective,
+            (true, true) if twist_x || twist_y => Topology::Klein,
+            (true, true) => Topology::Torus,
+            _ if twist_x || twist_y => Topology::Mobius,
+            _ => Topology::Cylinder,
+        }
+    }
+    /// Set the cycle and twist config of the map by the topology
+    pub fn set_topology(&mut self, topology: Topology) {
+        let ((cycle_x, cycle_y), (twist_x, twist_y)) = topology.get_config();
+        self.set_cycle(cycle_x, cycle_y);
+        self.set_twist(twist_x, twist_y);
+    }
+    /// Set the cycle and twist config of the map by the topology
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.set_topology(topology);
+        self
+    }
+    /// Wrap a point across the seams, returns the absolute coordinates inside the bounds.
+    pub fn wrap_point(&self, x: isize, y: isize) -> Option<(isize, isize)> {
+        let (i, j) = self.get_relative(x, y)?;
+        Some(relative_to_absolute(i, j, self.origin_x, self.origin_y))
+    }
+    /// Wrap a joint across the seams, the direction is flipped if the joint crosses a twisted seam.
+    pub fn wrap_joint(&self, joint: Joint) -> Option<Joint> {
+        let ((i, j), (flip_x, flip_y)) = self.wrap_relative(joint.source().0, joint.source().1)?;
+        if !self.has_relative(i, j) {
+            return None;
+        }
+        let (x, y) = relative_to_absolute(i, j, self.origin_x, self.origin_y);
+        let direction = match joint.get_direction() {
+            Direction::X(s) => Direction::X(s ^ flip_x),
+            Direction::Y(s) => Direction::Y(s ^ flip_y),
+        };
+        Some(Joint::new(x, y, direction))
+    }
+    /// Walk through a joint, returns the joint at the target point which keeps moving forward.
+    ///
+    /// Unlike [`Joint::target`], this respects the cycles, twists, holes and portals of the map.
+    pub fn joint_target(&self, joint: Joint) -> Option<Joint> {
+        if let Some(portal) = self.get_portal(joint) {
+            return self.wrap_joint(portal.get_target());
+        }
+        let (x, y) = joint.target();
+        self.wrap_joint(Joint::new(x, y, joint.get_direction()))
+    }
+}
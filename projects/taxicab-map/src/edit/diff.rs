@@ -0,0 +1,264 @@
+use super::*;
+use crate::io::binary::{MAX_AREA, invalid, read_signed, read_varint, write_signed, write_varint};
+use std::io::{Read, Result, Write};
+
+/// One step of a [`MapDiff`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MapEdit<T> {
+    /// Move the origin, see [`TaxicabMap::set_origin`]
+    Origin(isize, isize),
+    /// Set the bounds, new points take the fill value, see [`TaxicabMap::reframe`]
+    Bounds(Range<isize>, Range<isize>, T),
+    /// Mark a point as existing or not
+    Exists(isize, isize, bool),
+    /// Set the value of a point
+    Set(isize, isize, T),
+}
+
+/// Edits turning one map into another, applied in order.
+///
+/// Values, bounds, origin and the mask are compared, cycles, twists and portals are not.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MapDiff<T> {
+    edits: Vec<MapEdit<T>>,
+}
+
+impl<T> Default for MapDiff<T> {
+    fn default() -> Self {
+        Self { edits: vec![] }
+    }
+}
+
+impl<T> FromIterator<MapEdit<T>> for MapDiff<T> {
+    fn from_iter<I: IntoIterator<Item = MapEdit<T>>>(iter: I) -> Self {
+        Self { edits: iter.into_iter().collect() }
+    }
+}
+
+impl<T> MapDiff<T> {
+    /// Create an empty diff.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add an edit at the end.
+    pub fn push(&mut self, edit: MapEdit<T>) {
+        self.edits.push(edit)
+    }
+    /// Get all edits in order.
+    pub fn edits(&self) -> &[MapEdit<T>] {
+        &self.edits
+    }
+    /// Check if the diff changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+}
+
+impl<T: Clone + PartialEq> TaxicabMap<T> {
+    /// Get the edits that turn this map into the target.
+    ///
+    /// Maps of the same size whose cells match better after moving the origin get an origin edit,
+    /// otherwise cells are compared by their absolute coordinates.
+    pub fn diff(&self, target: &TaxicabMap<T>) -> MapDiff<T> {
+        let mut diff = MapDiff::new();
+        let mut base = self.clone();
+        let (target_x, target_y) = target.get_origin();
+        if self.get_size() == target.get_size() && self.get_origin() != target.get_origin() {
+            let moved = self.clone().with_origin(target_x, target_y);
+            if count_changes(&moved, target) < count_changes(self, target) {
+                diff.push(MapEdit::Origin(target_x, target_y));
+                base = moved;
+            }
+        }
+        let (x_range, y_range) = target.get_range();
+        if base.get_range() != (x_range.clone(), y_range.clone()) {
+            if let Some(fill) = target.any_value().or(self.any_value()) {
+                base.reframe(x_range.clone(), y_range.clone(), fill);
+                diff.push(MapEdit::Bounds(x_range.clone(), y_range.clone(), fill.clone()));
+            }
+        }
+        for (x, y) in x_range.flat_map(|x| y_range.clone().map(move |y| (x, y))) {
+            match (base.get_point(x, y), target.get_point(x, y)) {
+                (Some(_), None) => diff.push(MapEdit::Exists(x, y, false)),
+                (None, Some(v)) => {
+                    diff.push(MapEdit::Exists(x, y, true));
+                    diff.push(MapEdit::Set(x, y, v.clone()));
+                }
+                (Some(old), Some(new)) if old != new => diff.push(MapEdit::Set(x, y, new.clone())),
+                _ => {}
+            }
+        }
+        diff
+    }
+}
+
+/// Count the points that differ by absolute coordinates, inside the target.
+fn count_changes<T: PartialEq>(base: &TaxicabMap<T>, target: &TaxicabMap<T>) -> usize {
+    target.points_all().filter(|(x, y, v)| base.get_point(*x, *y) != Some(*v)).count()
+}
+
+impl<T: Clone> TaxicabMap<T> {
+    /// Get the edits recorded by the journal since it was started or last taken, the journal keeps recording.
+    ///
+    /// A moved origin is written first, then touched points are written with their current values,
+    /// and points gained by growing or reframing are all written.
+    /// Mask changes are not recorded. Returns `None` if the map is not journaling.
+    pub fn journal_diff(&mut self) -> Option<MapDiff<T>> {
+        let journal = self.take_journal()?;
+        let (points, (old_x, old_y)) = (journal.points, journal.bounds);
+        let mut diff = MapDiff::new();
+        if let Some((x, y)) = journal.origin {
+            diff.push(MapEdit::Origin(x, y));
+        }
+        let (x_range, y_range) = self.get_range();
+        if (old_x.clone(), old_y.clone()) != (x_range.clone(), y_range.clone()) {
+            if let Some(fill) = self.any_value() {
+                diff.push(MapEdit::Bounds(x_range.clone(), y_range.clone(), fill.clone()));
+            }
+            for (x, y, v) in self.points_all() {
+                if !old_x.contains(&x) || !old_y.contains(&y) {
+                    diff.push(MapEdit::Set(x, y, v.clone()))
+                }
+            }
+        }
+        // points gained by growing are already written
+        for (x, y) in points.into_iter().filter(|(x, y)| old_x.contains(x) && old_y.contains(y)) {
+            if let Some(v) = self.get_point(x, y) {
+                diff.push(MapEdit::Set(x, y, v.clone()))
+            }
+        }
+        Some(diff)
+    }
+    /// Apply the edits in order, returns false if an edit falls outside of the map.
+    ///
+    /// Origin moves that overflow and bounds that are reversed, overflow or cover more than 2<sup>26</sup> points
+    /// are skipped and return false as well.
+    pub fn apply_diff(&mut self, diff: &MapDiff<T>) -> bool {
+        self.apply_edits(diff, MAX_AREA)
+    }
+    /// Apply the edits in order like [`TaxicabMap::apply_diff`], with bounds up to `max_area` points.
+    pub(crate) fn apply_edits(&mut self, diff: &MapDiff<T>, max_area: usize) -> bool {
+        let mut ok = true;
+        for edit in &diff.edits {
+            ok &= match edit {
+                MapEdit::Origin(x, y) => {
+                    let (origin_x, origin_y) = self.get_origin();
+                    let fits = x.checked_sub(origin_x).is_some() && y.checked_sub(origin_y).is_some();
+                    if fits {
+                        self.set_origin(*x, *y);
+                    }
+                    fits
+                }
+                MapEdit::Bounds(x, y, fill) => {
+                    let fits = self.bounds_fit(x, y, max_area);
+                    if fits {
+                        self.reframe(x.clone(), y.clone(), fill);
+                    }
+                    fits
+                }
+                MapEdit::Exists(x, y, exists) => self.set_point_exists(*x, *y, *exists),
+                MapEdit::Set(x, y, v) => match self.mut_point(*x, *y) {
+                    Some(old) => {
+                        *old = v.clone();
+                        true
+                    }
+                    None => false,
+                },
+            }
+        }
+        ok
+    }
+    /// Check that the bounds can be reached from the current ones without overflow or a huge allocation.
+    fn bounds_fit(&self, x: &Range<isize>, y: &Range<isize>, max_area: usize) -> bool {
+        let ((origin_x, origin_y), (w, h)) = (self.get_origin(), self.get_size());
+        let side = |range: &Range<isize>, origin: isize, old: usize| {
+            let len = range.end.checked_sub(range.start).filter(|len| *len >= 0)?;
+            // old cells move by the difference of the origins
+            origin.checked_sub(range.start)?.checked_add(old as isize)?;
+            Some(len as usize)
+        };
+        match (side(x, origin_x, w), side(y, origin_y, h)) {
+            (Some(width), Some(height)) => width.checked_mul(height).is_some_and(|area| area <= max_area),
+            _ => false,
+        }
+    }
+}
+
+impl<T> MapDiff<T> {
+    /// Write the diff in a compact binary form, the function encodes each value.
+    pub fn write_binary<W, F>(&self, mut writer: W, encode: F) -> Result<()>
+    where
+        W: Write,
+        F: Fn(&T) -> Vec<u8>,
+    {
+        let out = &mut writer;
+        write_varint(out, self.edits.len() as u64)?;
+        let value = |out: &mut W, v: &T| {
+            let bytes = encode(v);
+            write_varint(out, bytes.len() as u64)?;
+            out.write_all(&bytes)
+        };
+        for edit in &self.edits {
+            match edit {
+                MapEdit::Origin(x, y) => {
+                    out.write_all(&[0])?;
+                    write_signed(out, *x)?;
+                    write_signed(out, *y)?;
+                }
+                MapEdit::Bounds(x, y, fill) => {
+                    out.write_all(&[1])?;
+                    for v in [x.start, x.end, y.start, y.end] {
+                        write_signed(out, v)?;
+                    }
+                    value(out, fill)?;
+                }
+                MapEdit::Exists(x, y, exists) => {
+                    out.write_all(&[2 + *exists as u8])?;
+                    write_signed(out, *x)?;
+                    write_signed(out, *y)?;
+                }
+                MapEdit::Set(x, y, v) => {
+                    out.write_all(&[4])?;
+                    write_signed(out, *x)?;
+                    write_signed(out, *y)?;
+                    value(out, v)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Read a diff written by [`MapDiff::write_binary`], the function decodes each value.
+    pub fn read_binary<R, F>(mut reader: R, decode: F) -> Result<Self>
+    where
+        R: Read,
+        F: Fn(&[u8]) -> Option<T>,
+    {
+        let input = &mut reader;
+        let mut diff = MapDiff::new();
+        let value = |input: &mut R| {
+            let len = read_varint(input)?;
+            let mut bytes = vec![];
+            input.take(len).read_to_end(&mut bytes)?;
+            match bytes.len() as u64 == len {
+                true => decode(&bytes).ok_or_else(|| invalid("rejected value")),
+                false => Err(std::io::ErrorKind::UnexpectedEof.into()),
+            }
+        };
+        for _ in 0..read_varint(input)? {
+            let mut tag = [0];
+            input.read_exact(&mut tag)?;
+            let edit = match tag[0] {
+                0 => MapEdit::Origin(read_signed(input)?, read_signed(input)?),
+                1 => {
+                    let (x0, x1, y0, y1) = (read_signed(input)?, read_signed(input)?, read_signed(input)?, read_signed(input)?);
+                    MapEdit::Bounds(x0..x1, y0..y1, value(input)?)
+                }
+                2 | 3 => MapEdit::Exists(read_signed(input)?, read_signed(input)?, tag[0] == 3),
+                4 => MapEdit::Set(read_signed(input)?, read_signed(input)?, value(input)?),
+                _ => return Err(invalid("unknown edit")),
+            };
+            diff.push(edit);
+        }
+        Ok(diff)
+    }
+}
@@ -0,0 +1,191 @@
+use super::*;
+use crate::{Anchor, Direction, MapDiff, MapEdit};
+use std::collections::VecDeque;
+
+/// Edit a map through named transactions that can be undone and redone.
+///
+/// Only the changed points are kept, structural edits keep the points they drop or gain, found by the journal of the map.
+#[derive(Clone, Debug)]
+pub struct MapHistory<T> {
+    map: TaxicabMap<T>,
+    undo: VecDeque<Transaction<T>>,
+    redo: Vec<Transaction<T>>,
+    open: Option<Transaction<T>>,
+    limit: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Transaction<T> {
+    name: String,
+    /// Pairs of (undo, redo) diffs in the order they were made
+    steps: Vec<(MapDiff<T>, MapDiff<T>)>,
+}
+
+impl<T: Clone + PartialEq> MapHistory<T> {
+    /// Create a history over the map, keeping the last 100 transactions.
+    pub fn new(map: TaxicabMap<T>) -> Self {
+        Self { map, undo: VecDeque::new(), redo: vec![], open: None, limit: 100 }
+    }
+    /// Set the number of transactions that can be undone.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self.trim();
+        self
+    }
+    /// Get the map in its current state.
+    pub fn get_map(&self) -> &TaxicabMap<T> {
+        &self.map
+    }
+    /// Get the map and drop the history.
+    pub fn into_map(self) -> TaxicabMap<T> {
+        self.map
+    }
+    /// Start a named transaction, an open transaction is committed first.
+    ///
+    /// Edits outside of a transaction are committed one by one, named after the edit.
+    pub fn begin(&mut self, name: &str) {
+        self.commit();
+        self.open = Some(Transaction { name: name.to_string(), steps: vec![] });
+    }
+    /// Commit the open transaction, the redo history is dropped if it changed anything.
+    pub fn commit(&mut self) {
+        let transaction = match self.open.take() {
+            Some(s) if !s.steps.is_empty() => s,
+            _ => return,
+        };
+        self.redo.clear();
+        self.undo.push_back(transaction);
+        self.trim();
+    }
+    /// Undo the last transaction, returns its name.
+    ///
+    /// The map only changes through the history, so the steps always fit the map they were recorded from.
+    pub fn undo(&mut self) -> Option<&str> {
+        self.commit();
+        let transaction = self.undo.pop_back()?;
+        for (undo, _) in transaction.steps.iter().rev() {
+            let applied = self.map.apply_edits(undo, usize::MAX);
+            debug_assert!(applied, "undo of {} does not fit the map", transaction.name);
+        }
+        self.redo.push(transaction);
+        self.redo.last().map(|t| t.name.as_str())
+    }
+    /// Redo the last undone transaction, returns its name, the steps always fit as in [`MapHistory::undo`].
+    pub fn redo(&mut self) -> Option<&str> {
+        self.commit();
+        let transaction = self.redo.pop()?;
+        for (_, redo) in &transaction.steps {
+            let applied = self.map.apply_edits(redo, usize::MAX);
+            debug_assert!(applied, "redo of {} does not fit the map", transaction.name);
+        }
+        self.undo.push_back(transaction);
+        self.undo.back().map(|t| t.name.as_str())
+    }
+    /// Get the names of the transactions that can be undone, the latest last.
+    pub fn undo_names(&self) -> impl Iterator<Item = &str> {
+        self.undo.iter().map(|t| t.name.as_str())
+    }
+    /// Get the names of the transactions that can be redone, the next one last.
+    pub fn redo_names(&self) -> impl Iterator<Item = &str> {
+        self.redo.iter().map(|t| t.name.as_str())
+    }
+    /// Set the value of a point, the map grows by its growth policy, see [`TaxicabMap::set_point`].
+    pub fn set_point(&mut self, x: isize, y: isize, value: T) -> bool {
+        match self.map.get_point(x, y).cloned() {
+            Some(old) if old == value => true,
+            Some(old) => {
+                self.map.set_point(x, y, value.clone());
+                self.push_step(
+                    "set_point",
+                    [MapEdit::Set(x, y, old)].into_iter().collect(),
+                    [MapEdit::Set(x, y, value)].into_iter().collect(),
+                );
+                true
+            }
+            // the map may grow, no point is dropped
+            None => self.record("set_point", self.map.get_range(), |map| map.set_point(x, y, value)),
+        }
+    }
+    /// Extend the map in a direction, see [`TaxicabMap::extend`].
+    pub fn extend(&mut self, direction: Direction, size: usize, fill: &T) {
+        self.record("extend", self.map.get_range(), |map| map.extend(direction, size, fill))
+    }
+    /// Resize the map, see [`TaxicabMap::resize`].
+    pub fn resize(&mut self, width: usize, height: usize, anchor_x: Anchor, anchor_y: Anchor, fill: &T) {
+        let ((x, y), (w, h)) = (self.map.get_origin(), self.map.get_size());
+        let x = x - anchor_x.offset(w as isize, width as isize);
+        let y = y - anchor_y.offset(h as isize, height as isize);
+        let keep = (x..x + width as isize, y..y + height as isize);
+        self.record("resize", keep, |map| map.resize(width, height, anchor_x, anchor_y, fill))
+    }
+    /// Copy all points of the source, moved by the offset, points outside of the map are skipped.
+    pub fn paste(&mut self, source: &TaxicabMap<T>, dx: isize, dy: isize) {
+        let mut undo = vec![];
+        let mut redo = MapDiff::new();
+        for (x, y, value) in source.points_all() {
+            let (x, y) = (x + dx, y + dy);
+            if let Some(old) = self.map.mut_point(x, y) {
+                if old != value {
+                    undo.push(MapEdit::Set(x, y, std::mem::replace(old, value.clone())));
+                    redo.push(MapEdit::Set(x, y, value.clone()));
+                }
+            }
+        }
+        // undo in reverse, in case the source covers a point twice on a cyclic map
+        self.push_step("paste", undo.into_iter().rev().collect(), redo)
+    }
+    /// Record a structural edit that keeps the points in `keep`, only the dropped and gained points are stored.
+    ///
+    /// The journal of the map finds the gained and touched points, the dropped points are read before the edit.
+    fn record<R, F>(&mut self, name: &str, keep: (Range<isize>, Range<isize>), edit: F) -> R
+    where
+        F: FnOnce(&mut TaxicabMap<T>) -> R,
+    {
+        let (old_x, old_y) = self.map.get_range();
+        let mut dropped = vec![];
+        let rows = old_y.clone().filter(|y| !keep.1.contains(y)).collect::<Vec<_>>();
+        for x in old_x.clone() {
+            let column: Box<dyn Iterator<Item = isize>> = match keep.0.contains(&x) {
+                true => Box::new(rows.iter().copied()),
+                false => Box::new(old_y.clone()),
+            };
+            for y in column {
+                match self.map.get_point(x, y) {
+                    Some(v) => dropped.push(MapEdit::Set(x, y, v.clone())),
+                    None => dropped.push(MapEdit::Exists(x, y, false)),
+                }
+            }
+        }
+        let fill = self.map.any_value().cloned();
+        self.map.start_journal();
+        let out = edit(&mut self.map);
+        let redo = self.map.journal_diff().unwrap_or_default();
+        self.map.stop_journal();
+        let mut undo = MapDiff::new();
+        if self.map.get_range() != (old_x.clone(), old_y.clone()) {
+            if let Some(fill) = fill.or_else(|| self.map.any_value().cloned()) {
+                undo.push(MapEdit::Bounds(old_x, old_y, fill));
+            }
+        }
+        dropped.into_iter().for_each(|edit| undo.push(edit));
+        self.push_step(name, undo, redo);
+        out
+    }
+    fn push_step(&mut self, name: &str, undo: MapDiff<T>, redo: MapDiff<T>) {
+        if redo.is_empty() {
+            return;
+        }
+        match &mut self.open {
+            Some(transaction) => transaction.steps.push((undo, redo)),
+            None => {
+                self.open = Some(Transaction { name: name.to_string(), steps: vec![(undo, redo)] });
+                self.commit();
+            }
+        }
+    }
+    fn trim(&mut self) {
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+}
@@ -0,0 +1,7 @@
+use crate::TaxicabMap;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+pub mod diff;
+pub mod history;
+pub mod tracker;
@@ -0,0 +1,147 @@
+use super::*;
+use crate::MutGetTaxicabPoints;
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, Index, IndexMut},
+};
+
+/// A function called with the position, the old value and the new value of each changed point.
+type Subscriber<T> = Box<dyn FnMut(isize, isize, &T, &T)>;
+
+/// Track the points changed since the last [`TrackedMap::take_dirty`], reading goes through [`Deref`].
+///
+/// Every mutable access keeps the old value of the point, so changes through `IndexMut` and
+/// [`TrackedMap::points_mut`] are seen too, and points written back to their old value are not dirty.
+/// Subscribers are not called when a point changes, only when [`TrackedMap::take_dirty`] compares the old values.
+pub struct TrackedMap<T> {
+    map: TaxicabMap<T>,
+    touched: BTreeMap<(isize, isize), T>,
+    subscribers: Vec<Subscriber<T>>,
+}
+
+impl<T> Deref for TrackedMap<T> {
+    type Target = TaxicabMap<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<T: Clone + PartialEq> TrackedMap<T> {
+    /// Start tracking the map, nothing is dirty yet.
+    pub fn new(map: TaxicabMap<T>) -> Self {
+        Self { map, touched: BTreeMap::new(), subscribers: vec![] }
+    }
+    /// Get the map and stop tracking.
+    pub fn into_map(self) -> TaxicabMap<T> {
+        self.map
+    }
+    /// Add a function called with the position, the old value and the new value of each change.
+    ///
+    /// It is called from [`TrackedMap::take_dirty`], not at the time of the change.
+    pub fn subscribe<F>(&mut self, f: F)
+    where
+        F: FnMut(isize, isize, &T, &T) + 'static,
+    {
+        self.subscribers.push(Box::new(f))
+    }
+    /// Get the mutable value of a point, the old value is kept to find out if it changed.
+    pub fn mut_point(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        let (x, y) = self.map.wrap_point(x, y)?;
+        let value = self.map.mut_point(x, y)?;
+        self.touched.entry((x, y)).or_insert_with(|| value.clone());
+        Some(value)
+    }
+    /// Set the value of a point, returns false if the point does not exist, the map never grows.
+    pub fn set_point(&mut self, x: isize, y: isize, value: T) -> bool {
+        match self.mut_point(x, y) {
+            Some(v) => {
+                *v = value;
+                true
+            }
+            None => false,
+        }
+    }
+    /// Mutable traversal of all points, the old value of each point is kept when it is yielded.
+    pub fn points_mut(&mut self) -> impl Iterator<Item = (isize, isize, &mut T)> {
+        TrackedPointsMut { points: self.map.points_mut(), touched: &mut self.touched }
+    }
+    /// Check if any point was touched since the last take, the values may be unchanged.
+    pub fn is_touched(&self) -> bool {
+        !self.touched.is_empty()
+    }
+    /// Take the changed points coalesced into rectangles, and notify the subscribers of each change.
+    pub fn take_dirty(&mut self) -> Vec<(Range<isize>, Range<isize>)> {
+        let mut dirty = vec![];
+        for ((x, y), old) in std::mem::take(&mut self.touched) {
+            match self.map.get_point(x, y) {
+                Some(new) if *new != old => {
+                    for subscriber in &mut self.subscribers {
+                        subscriber(x, y, &old, new)
+                    }
+                    dirty.push((x, y))
+                }
+                _ => {}
+            }
+        }
+        coalesce(dirty)
+    }
+}
+
+struct TrackedPointsMut<'a, T> {
+    points: MutGetTaxicabPoints<'a, T>,
+    touched: &'a mut BTreeMap<(isize, isize), T>,
+}
+
+impl<'a, T: Clone> Iterator for TrackedPointsMut<'a, T> {
+    type Item = (isize, isize, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, y, value) = self.points.next()?;
+        self.touched.entry((x, y)).or_insert_with(|| value.clone());
+        Some((x, y, value))
+    }
+}
+
+impl<T: Clone + PartialEq> Index<(isize, isize)> for TrackedMap<T> {
+    type Output = T;
+
+    fn index(&self, index: (isize, isize)) -> &Self::Output {
+        &self.map[index]
+    }
+}
+
+impl<T: Clone + PartialEq> IndexMut<(isize, isize)> for TrackedMap<T> {
+    fn index_mut(&mut self, index: (isize, isize)) -> &mut Self::Output {
+        match self.mut_point(index.0, index.1) {
+            Some(s) => s,
+            None => panic!("Index out of bounds: {:?}", index),
+        }
+    }
+}
+
+/// Merge points into runs along each row, then stack runs of the same span on adjacent rows.
+fn coalesce(mut points: Vec<(isize, isize)>) -> Vec<(Range<isize>, Range<isize>)> {
+    points.sort_by_key(|(x, y)| (*y, *x));
+    let mut runs: Vec<(Range<isize>, isize)> = vec![];
+    for (x, y) in points {
+        match runs.last_mut() {
+            Some((run, row)) if *row == y && run.end == x => run.end += 1,
+            _ => runs.push((x..x + 1, y)),
+        }
+    }
+    let mut out: Vec<(Range<isize>, Range<isize>)> = vec![];
+    // rectangles still open at the previous row, by their span
+    let mut open: BTreeMap<(isize, isize), usize> = BTreeMap::new();
+    for (run, y) in runs {
+        let key = (run.start, run.end);
+        match open.get(&key) {
+            Some(&i) if out[i].1.end == y => out[i].1.end += 1,
+            _ => {
+                open.insert(key, out.len());
+                out.push((run, y..y + 1));
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,299 @@
+use super::*;
+use crate::DiamondPoints;
+use std::collections::VecDeque;
+
+/// The points around a cell that are counted by a cellular automaton.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Neighborhood {
+    /// All points within the taxicab distance, a diamond.
+    VonNeumann(usize),
+    /// All points within the chessboard distance, a square.
+    Moore(usize),
+}
+
+impl Neighborhood {
+    /// Get the offsets of the neighborhood, the center is excluded.
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        match *self {
+            Neighborhood::VonNeumann(r) => (1..=r as isize).flat_map(|n| DiamondPoints::new(0, 0, n)).collect(),
+            Neighborhood::Moore(r) => {
+                let r = r as isize;
+                (-r..=r).flat_map(|x| (-r..=r).map(move |y| (x, y))).filter(|p| *p != (0, 0)).collect()
+            }
+        }
+    }
+}
+
+/// Cellular automaton cave generator with birth and survival rules.
+#[derive(Clone, Debug)]
+pub struct CaveAutomaton {
+    fill: f64,
+    steps: usize,
+    birth: Vec<usize>,
+    survival: Vec<usize>,
+    neighborhood: Neighborhood,
+}
+
+impl Default for CaveAutomaton {
+    fn default() -> Self {
+        Self {
+            fill: 0.45,
+            steps: 5,
+            birth: vec![5, 6, 7, 8],
+            survival: vec![4, 5, 6, 7, 8],
+            neighborhood: Neighborhood::Moore(1),
+        }
+    }
+}
+
+impl CaveAutomaton {
+    /// Set the ratio of walls in the initial noise, clamped to `0.0..=1.0`, `NaN` means no walls.
+    pub fn with_fill(mut self, ratio: f64) -> Self {
+        self.fill = if ratio.is_nan() { 0.0 } else { ratio.clamp(0.0, 1.0) };
+        self
+    }
+    /// Set the number of smoothing steps.
+    pub fn with_steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+    /// Set the rule by the count of walls around: a floor becomes a wall if the count is in `birth`,
+    /// a wall stays a wall if the count is in `survival`.
+    pub fn with_rule(mut self, birth: &[usize], survival: &[usize]) -> Self {
+        self.birth = birth.to_vec();
+        self.survival = survival.to_vec();
+        self
+    }
+    /// Set the neighborhood counted by the rule, points out of the map count as walls.
+    pub fn with_neighborhood(mut self, neighborhood: Neighborhood) -> Self {
+        self.neighborhood = neighborhood;
+        self
+    }
+    /// Generate a cave into the map, all floors are connected.
+    pub fn generate<T, R>(&self, map: &mut TaxicabMap<T>, wall: &T, floor: &T, rng: &mut R)
+    where
+        T: Clone + PartialEq,
+        R: Rng + ?Sized,
+    {
+        let offsets = self.neighborhood.offsets();
+        let mut walls: BTreeMap<(isize, isize), bool> =
+            map.points_all().map(|(x, y, _)| ((x, y), rng.gen_bool(self.fill))).collect();
+        for _ in 0..self.steps {
+            let mut next = BTreeMap::new();
+            for (&(x, y), &is_wall) in walls.iter() {
+                let count = offsets
+                    .iter()
+                    .filter(|(dx, dy)| match map.wrap_point(x + dx, y + dy) {
+                        Some(p) => walls[&p],
+                        None => true,
+                    })
+                    .count();
+                let rule = if is_wall { &self.survival } else { &self.birth };
+                next.insert((x, y), rule.contains(&count));
+            }
+            walls = next;
+        }
+        for ((x, y), is_wall) in walls {
+            map.set_point(x, y, if is_wall { wall.clone() } else { floor.clone() });
+        }
+        map.connect_floors(floor);
+    }
+}
+
+/// Binary space partition dungeon generator, rooms connected by corridors.
+#[derive(Copy, Clone, Debug)]
+pub struct BspDungeon {
+    min_leaf: usize,
+    min_room: usize,
+}
+
+impl Default for BspDungeon {
+    fn default() -> Self {
+        Self { min_leaf: 8, min_room: 3 }
+    }
+}
+
+impl BspDungeon {
+    /// Set the minimum size of a partition and the minimum size of a room in it.
+    pub fn new(min_leaf: usize, min_room: usize) -> Self {
+        Self { min_leaf: min_leaf.max(3), min_room: min_room.max(1) }
+    }
+    /// Generate a dungeon into the map, all floors are connected.
+    pub fn generate<T, R>(&self, map: &mut TaxicabMap<T>, wall: &T, floor: &T, rng: &mut R)
+    where
+        T: Clone + PartialEq,
+        R: Rng + ?Sized,
+    {
+        for (_, _, v) in map.points_mut() {
+            *v = wall.clone();
+        }
+        let (x, y) = map.get_range();
+        self.split(map, x, y, floor, rng);
+        map.connect_floors(floor);
+    }
+    /// Split the area or place a room, returns the center of a room in the area.
+    fn split<T, R>(&self, map: &mut TaxicabMap<T>, x: Range<isize>, y: Range<isize>, floor: &T, rng: &mut R) -> (isize, isize)
+    where
+        T: Clone,
+        R: Rng + ?Sized,
+    {
+        let leaf = self.min_leaf as isize;
+        let (w, h) = (x.end - x.start, y.end - y.start);
+        let vertical = w >= 2 * leaf && (w >= h || h < 2 * leaf);
+        let horizontal = h >= 2 * leaf;
+        let (a, b) = if vertical {
+            let cut = rng.gen_range(x.start + leaf..=x.end - leaf);
+            (self.split(map, x.start..cut, y.clone(), floor, rng), self.split(map, cut..x.end, y, floor, rng))
+        }
+        else if horizontal {
+            let cut = rng.gen_range(y.start + leaf..=y.end - leaf);
+            (self.split(map, x.clone(), y.start..cut, floor, rng), self.split(map, x, cut..y.end, floor, rng))
+        }
+        else {
+            return self.room(map, x, y, floor, rng);
+        };
+        carve_corridor(map, a, b, floor);
+        if rng.gen_bool(0.5) { a } else { b }
+    }
+    fn room<T, R>(&self, map: &mut TaxicabMap<T>, x: Range<isize>, y: Range<isize>, floor: &T, rng: &mut R) -> (isize, isize)
+    where
+        T: Clone,
+        R: Rng + ?Sized,
+    {
+        let room = |start: isize, end: isize, rng: &mut R| {
+            // keep a wall margin of one cell
+            let space = (end - start - 2).max(1);
+            let size = rng.gen_range(space.min(self.min_room as isize)..=space);
+            let offset = rng.gen_range(0..=space - size);
+            start + 1 + offset..start + 1 + offset + size
+        };
+        let (x, y) = (room(x.start, x.end, rng), room(y.start, y.end, rng));
+        for (i, j) in x.clone().flat_map(|i| y.clone().map(move |j| (i, j))) {
+            map.set_point(i, j, floor.clone());
+        }
+        ((x.start + x.end) / 2, (y.start + y.end) / 2)
+    }
+}
+
+/// Random walkers carving floors until enough of the map is covered.
+#[derive(Copy, Clone, Debug)]
+pub struct DrunkardWalk {
+    coverage: f64,
+    walkers: usize,
+}
+
+impl Default for DrunkardWalk {
+    fn default() -> Self {
+        Self { coverage: 0.4, walkers: 1 }
+    }
+}
+
+impl DrunkardWalk {
+    /// Set the ratio of floors to carve.
+    pub fn new(coverage: f64) -> Self {
+        Self { coverage: coverage.clamp(0.0, 1.0), walkers: 1 }
+    }
+    /// Set the number of walkers, each walker starts from a random floor.
+    pub fn with_walkers(mut self, walkers: usize) -> Self {
+        self.walkers = walkers.max(1);
+        self
+    }
+    /// Generate caves into the map, all floors are connected.
+    pub fn generate<T, R>(&self, map: &mut TaxicabMap<T>, wall: &T, floor: &T, rng: &mut R)
+    where
+        T: Clone + PartialEq,
+        R: Rng + ?Sized,
+    {
+        let points = map.points_all().map(|(x, y, _)| (x, y)).collect::<Vec<_>>();
+        for (x, y) in points.iter() {
+            map.set_point(*x, *y, wall.clone());
+        }
+        let start = match points.choose(rng) {
+            Some(s) => *s,
+            None => return,
+        };
+        let target = ((points.len() as f64 * self.coverage).ceil() as usize).max(1);
+        let budget = points.len() * 20;
+        let mut carved = vec![start];
+        map.set_point(start.0, start.1, floor.clone());
+        for _ in 0..self.walkers {
+            let mut current = *carved.choose(rng).unwrap_or(&start);
+            for _ in 0..budget {
+                if carved.len() >= target {
+                    break;
+                }
+                current = match map.steps_nearby(current.0, current.1).choose(rng) {
+                    Some((_, next)) => *next,
+                    None => break,
+                };
+                if map.get_point(current.0, current.1) != Some(floor) {
+                    map.set_point(current.0, current.1, floor.clone());
+                    carved.push(current);
+                }
+            }
+        }
+        map.connect_floors(floor);
+    }
+}
+
+fn carve_corridor<T: Clone>(map: &mut TaxicabMap<T>, (x1, y1): (isize, isize), (x2, y2): (isize, isize), floor: &T) {
+    for x in x1.min(x2)..=x1.max(x2) {
+        map.set_point(x, y1, floor.clone());
+    }
+    for y in y1.min(y2)..=y1.max(y2) {
+        map.set_point(x2, y, floor.clone());
+    }
+}
+
+impl<T: Clone + PartialEq> TaxicabMap<T> {
+    /// Carve corridors until all floors are connected, returns the number of carved points.
+    ///
+    /// One breadth first search grows from the first floor region, each region it reaches is joined by the
+    /// corridor it was found through and then searched from as well.
+    pub fn connect_floors(&mut self, floor: &T) -> usize {
+        let mut connected = match self.points_all().find(|(_, _, v)| *v == floor) {
+            Some((x, y, _)) => self.flood_floors((x, y), floor),
+            None => return 0,
+        };
+        let mut carved = 0;
+        let mut parent = BTreeMap::new();
+        let mut queue: VecDeque<_> = connected.iter().copied().collect();
+        while let Some(current) = queue.pop_front() {
+            for (_, next) in self.steps_nearby(current.0, current.1) {
+                if connected.contains(&next) || parent.contains_key(&next) {
+                    continue;
+                }
+                parent.insert(next, current);
+                if self.get_point(next.0, next.1) != Some(floor) {
+                    queue.push_back(next);
+                    continue;
+                }
+                // an isolated floor, carve the way back and search from its whole region
+                let mut back = current;
+                while connected.insert(back) {
+                    self.set_point(back.0, back.1, floor.clone());
+                    carved += 1;
+                    back = parent[&back];
+                }
+                for point in self.flood_floors(next, floor) {
+                    if connected.insert(point) {
+                        queue.push_back(point);
+                    }
+                }
+            }
+        }
+        carved
+    }
+    fn flood_floors(&self, start: (isize, isize), floor: &T) -> BTreeSet<(isize, isize)> {
+        let mut visited = BTreeSet::from([start]);
+        let mut stack = vec![start];
+        while let Some((x, y)) = stack.pop() {
+            for (_, next) in self.steps_nearby(x, y) {
+                if self.get_point(next.0, next.1) == Some(floor) && visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        visited
+    }
+}
@@ -0,0 +1,287 @@
+use super::*;
+
+/// The algorithm used to generate a perfect maze.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MazeAlgorithm {
+    /// Randomized depth first search, long and winding corridors.
+    Backtracker,
+    /// Randomized Prim's algorithm, many short dead ends.
+    Prim,
+    /// Randomized Kruskal's algorithm.
+    Kruskal,
+    /// Wilson's algorithm, a uniform spanning tree.
+    Wilson,
+    /// Eller's algorithm, row by row.
+    Eller,
+    /// Binary tree algorithm, always carve toward `X(true)` or `Y(true)`.
+    BinaryTree,
+    /// Sidewinder algorithm, row by row with runs toward `X(true)`.
+    Sidewinder,
+}
+
+impl<T> TaxicabMap<T> {
+    /// Generate a perfect maze over all points of the map, returns the open joints from both sides of each passage.
+    ///
+    /// The graph algorithms follow cycles, twists, holes and portals of the map, so toroidal mazes are possible.
+    /// The row based algorithms (`Eller`, `BinaryTree` and `Sidewinder`) work on the full rectangle, `Eller` also
+    /// follows the `cycle_x` seam.
+    pub fn maze_joints<R: Rng + ?Sized>(&self, algorithm: MazeAlgorithm, rng: &mut R) -> BTreeSet<Joint> {
+        let mut maze = MazeBuilder { map: self, open: BTreeSet::new() };
+        match algorithm {
+            MazeAlgorithm::Backtracker => maze.backtracker(rng),
+            MazeAlgorithm::Prim => maze.prim(rng),
+            MazeAlgorithm::Kruskal => maze.kruskal(rng),
+            MazeAlgorithm::Wilson => maze.wilson(rng),
+            MazeAlgorithm::Eller => maze.eller(rng),
+            MazeAlgorithm::BinaryTree => maze.binary_tree(rng),
+            MazeAlgorithm::Sidewinder => maze.sidewinder(rng),
+        }
+        maze.open
+    }
+    /// Generate a perfect maze as a cell map, see [`TaxicabMap::maze_joints`] and [`TaxicabMap::expand_joints`].
+    pub fn maze_map<U: Clone, R: Rng + ?Sized>(
+        &self,
+        algorithm: MazeAlgorithm,
+        rng: &mut R,
+        wall: &U,
+        floor: &U,
+    ) -> TaxicabMap<U> {
+        self.expand_joints(&self.maze_joints(algorithm, rng), wall, floor)
+    }
+    /// Draw the open joints as a cell map, point `(x, y)` becomes `(2x + 1, 2y + 1)` and the joints become the cells between.
+    ///
+    /// Non-cyclic axes get a wall border, twists are not kept.
+    pub fn expand_joints<U: Clone>(&self, joints: &BTreeSet<Joint>, wall: &U, floor: &U) -> TaxicabMap<U> {
+        let (w, h) = self.get_size();
+        let (cycle_x, cycle_y) = self.get_cycle();
+        let (origin_x, origin_y) = self.get_origin();
+        let width = if cycle_x { 2 * w } else { 2 * w + 1 };
+        let height = if cycle_y { 2 * h } else { 2 * h + 1 };
+        let mut out =
+            TaxicabMap::rectangle(width, height, wall).with_origin(2 * origin_x, 2 * origin_y).with_cycle(cycle_x, cycle_y);
+        for (x, y, _) in self.points_all() {
+            out.set_point(2 * x + 1, 2 * y + 1, floor.clone());
+        }
+        for joint in joints {
+            let (x, y) = joint.source();
+            let (dx, dy) = match joint.get_direction() {
+                Direction::X(s) => (if s { 1 } else { -1 }, 0),
+                Direction::Y(s) => (0, if s { 1 } else { -1 }),
+            };
+            out.set_point(2 * x + 1 + dx, 2 * y + 1 + dy, floor.clone());
+        }
+        out
+    }
+}
+
+struct MazeBuilder<'a, T> {
+    map: &'a TaxicabMap<T>,
+    open: BTreeSet<Joint>,
+}
+
+impl<'a, T> MazeBuilder<'a, T> {
+    fn carve(&mut self, joint: Joint) {
+        let joint = match self.map.wrap_joint(joint) {
+            Some(s) => s,
+            None => return,
+        };
+        if let Some(target) = self.map.joint_target(joint) {
+            self.open.insert(joint);
+            self.open.insert(!target);
+        }
+    }
+    fn points(&self) -> Vec<(isize, isize)> {
+        self.map.points_all().map(|(x, y, _)| (x, y)).collect()
+    }
+    fn backtracker<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let mut visited = BTreeSet::new();
+        for start in self.points() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut stack = vec![start];
+            while let Some(&(x, y)) = stack.last() {
+                let next = self.map.steps_nearby(x, y).into_iter().filter(|(_, p)| !visited.contains(p)).collect::<Vec<_>>();
+                match next.choose(rng) {
+                    Some(&(joint, point)) => {
+                        self.carve(joint);
+                        visited.insert(point);
+                        stack.push(point);
+                    }
+                    None => {
+                        stack.pop();
+                    }
+                }
+            }
+        }
+    }
+    fn prim<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let mut visited = BTreeSet::new();
+        for start in self.points() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut frontier = self.map.steps_nearby(start.0, start.1);
+            while !frontier.is_empty() {
+                let (joint, point) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+                if !visited.insert(point) {
+                    continue;
+                }
+                self.carve(joint);
+                frontier.extend(self.map.steps_nearby(point.0, point.1).into_iter().filter(|(_, p)| !visited.contains(p)));
+            }
+        }
+    }
+    fn kruskal<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let mut edges = vec![];
+        for (x, y) in self.points() {
+            edges.extend(self.map.steps_nearby(x, y).into_iter().map(|(joint, point)| (joint, (x, y), point)));
+        }
+        edges.shuffle(rng);
+        let mut sets = UnionFind::new();
+        for (joint, from, to) in edges {
+            if sets.union(from, to) {
+                self.carve(joint);
+            }
+        }
+    }
+    fn wilson<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let mut points = self.points();
+        points.shuffle(rng);
+        // one-way portals make steps directed, walks may only enter points that can still reach the tree
+        let mut sources: BTreeMap<(isize, isize), Vec<(isize, isize)>> = BTreeMap::new();
+        for &(x, y) in points.iter() {
+            for (_, point) in self.map.steps_nearby(x, y) {
+                sources.entry(point).or_default().push((x, y));
+            }
+        }
+        let mut in_tree = BTreeSet::new();
+        for root in points.iter() {
+            if in_tree.contains(root) {
+                continue;
+            }
+            // every point that cannot reach an earlier tree gets its own root
+            in_tree.insert(*root);
+            let reaching = reaching(&sources, *root);
+            for start in points.iter().filter(|p| reaching.contains(p)) {
+                // loop erased random walk, only the last exit of each point is remembered
+                let mut exits = BTreeMap::new();
+                let mut current = *start;
+                while !in_tree.contains(&current) {
+                    let steps = self.map.steps_nearby(current.0, current.1);
+                    let steps = steps.iter().filter(|(_, p)| reaching.contains(p) || in_tree.contains(p)).collect::<Vec<_>>();
+                    match steps.choose(rng) {
+                        Some(&&(joint, next)) => {
+                            exits.insert(current, joint);
+                            current = next;
+                        }
+                        // a dead end starts a tree of its own
+                        None => {
+                            in_tree.insert(current);
+                        }
+                    }
+                }
+                let mut current = *start;
+                while !in_tree.contains(&current) {
+                    in_tree.insert(current);
+                    let joint = exits[&current];
+                    self.carve(joint);
+                    current = self.map.joint_target(joint).map(|j| j.source()).unwrap_or(current);
+                }
+            }
+        }
+    }
+    fn rows(&self) -> Vec<Vec<(isize, isize)>> {
+        let (x_range, y_range) = self.map.get_range();
+        y_range.map(|y| x_range.clone().map(|x| (x, y)).filter(|(x, y)| self.map.has_point(*x, *y)).collect()).collect()
+    }
+    fn eller<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let rows = self.rows();
+        let (x_range, _) = self.map.get_range();
+        let mut sets = UnionFind::new();
+        for (index, row) in rows.iter().enumerate() {
+            let last = index + 1 == rows.len();
+            // join cells in the row, the last row joins all different sets
+            for &(x, y) in row {
+                let joint = Joint::new(x, y, Direction::X(true));
+                let target = match self.map.joint_target(joint) {
+                    Some(s) if s.source().1 == y && (x + 1 < x_range.end || self.map.get_cycle().0) => s.source(),
+                    _ => continue,
+                };
+                if sets.find((x, y)) != sets.find(target) && (last || rng.gen_bool(0.5)) {
+                    sets.union((x, y), target);
+                    self.carve(joint);
+                }
+            }
+            if last {
+                break;
+            }
+            // each set goes down at least once
+            let mut groups: BTreeMap<(isize, isize), Vec<(isize, isize)>> = BTreeMap::new();
+            for &point in row {
+                groups.entry(sets.find(point)).or_default().push(point);
+            }
+            for (_, mut group) in groups {
+                group.shuffle(rng);
+                let count = rng.gen_range(1..=group.len());
+                for &(x, y) in group.iter().take(count) {
+                    if self.map.has_point(x, y + 1) {
+                        sets.union((x, y), (x, y + 1));
+                        self.carve(Joint::new(x, y, Direction::Y(true)));
+                    }
+                }
+            }
+        }
+    }
+    fn binary_tree<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let (x_range, y_range) = self.map.get_range();
+        for (x, y) in self.points() {
+            let mut choices = Vec::with_capacity(2);
+            if x + 1 < x_range.end && self.map.has_point(x + 1, y) {
+                choices.push(Direction::X(true));
+            }
+            if y + 1 < y_range.end && self.map.has_point(x, y + 1) {
+                choices.push(Direction::Y(true));
+            }
+            if let Some(direction) = choices.choose(rng) {
+                self.carve(Joint::new(x, y, *direction));
+            }
+        }
+    }
+    fn sidewinder<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let (x_range, y_range) = self.map.get_range();
+        for row in self.rows() {
+            let mut run = vec![];
+            for (x, y) in row {
+                run.push((x, y));
+                let can_east = x + 1 < x_range.end && self.map.has_point(x + 1, y);
+                let can_north = y + 1 < y_range.end;
+                if can_east && (!can_north || rng.gen_bool(0.5)) {
+                    self.carve(Joint::new(x, y, Direction::X(true)));
+                    continue;
+                }
+                if can_north {
+                    let candidates = run.iter().filter(|(x, y)| self.map.has_point(*x, y + 1)).copied().collect::<Vec<_>>();
+                    if let Some(&(x, y)) = candidates.choose(rng) {
+                        self.carve(Joint::new(x, y, Direction::Y(true)));
+                    }
+                }
+                run.clear();
+            }
+        }
+    }
+}
+
+/// Find all points that can walk to the target, given the sources of the steps into each point.
+fn reaching(sources: &BTreeMap<(isize, isize), Vec<(isize, isize)>>, target: (isize, isize)) -> BTreeSet<(isize, isize)> {
+    let mut visited = BTreeSet::from([target]);
+    let mut stack = vec![target];
+    while let Some(point) = stack.pop() {
+        for &source in sources.get(&point).into_iter().flatten() {
+            if visited.insert(source) {
+                stack.push(source);
+            }
+        }
+    }
+    visited
+}
@@ -0,0 +1,55 @@
+use crate::{Direction, Joint, TaxicabMap};
+use rand::{Rng, seq::SliceRandom};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+pub mod cave;
+pub mod maze;
+pub mod noise;
+pub mod wfc;
+
+/// Disjoint sets over the points of a map.
+pub(crate) struct UnionFind {
+    parent: BTreeMap<(isize, isize), (isize, isize)>,
+}
+
+impl UnionFind {
+    pub fn new() -> Self {
+        Self { parent: BTreeMap::new() }
+    }
+    pub fn find(&mut self, point: (isize, isize)) -> (isize, isize) {
+        let parent = *self.parent.entry(point).or_insert(point);
+        if parent == point {
+            return point;
+        }
+        let root = self.find(parent);
+        self.parent.insert(point, root);
+        root
+    }
+    /// Merge two sets, returns false if they are already the same set.
+    pub fn union(&mut self, a: (isize, isize), b: (isize, isize)) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        self.parent.insert(a, b);
+        true
+    }
+}
+
+impl<T> TaxicabMap<T> {
+    /// Get all points that can be reached in one step with the joint to them, in wrapped coordinates.
+    pub(crate) fn steps_nearby(&self, x: isize, y: isize) -> Vec<(Joint, (isize, isize))> {
+        let mut out = Vec::with_capacity(4);
+        for joint in self.joints_nearby(x, y) {
+            if let (Some(joint), Some(target)) = (self.wrap_joint(joint), self.joint_target(joint)) {
+                if target.source() != (x, y) {
+                    out.push((joint, target.source()))
+                }
+            }
+        }
+        out
+    }
+}
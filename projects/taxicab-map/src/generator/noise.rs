@@ -0,0 +1,138 @@
+use super::*;
+
+/// The lattice noise used by each octave of a noise field.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum NoiseKind {
+    /// Random values on the lattice, smoothly interpolated.
+    Value,
+    /// Random gradients on the lattice, known as Perlin noise.
+    Perlin,
+}
+
+/// Coherent fractal noise sampled over absolute coordinates, values are in `[-1, 1]`.
+#[derive(Copy, Clone, Debug)]
+pub struct NoiseField {
+    kind: NoiseKind,
+    seed: u64,
+    frequency: f64,
+    octaves: usize,
+    persistence: f64,
+    lacunarity: f64,
+}
+
+impl NoiseField {
+    /// Create a single octave noise field with frequency `0.1`.
+    pub fn new(kind: NoiseKind, seed: u64) -> Self {
+        Self { kind, seed, frequency: 0.1, octaves: 1, persistence: 0.5, lacunarity: 2.0 }
+    }
+    /// Set the frequency of the first octave, the number of lattice cells per map cell.
+    pub fn with_frequency(mut self, frequency: f64) -> Self {
+        self.frequency = frequency;
+        self
+    }
+    /// Set the number of octaves of fractal Brownian motion.
+    pub fn with_octaves(mut self, octaves: usize) -> Self {
+        self.octaves = octaves.max(1);
+        self
+    }
+    /// Set the amplitude multiplier of each octave.
+    pub fn with_persistence(mut self, persistence: f64) -> Self {
+        self.persistence = persistence;
+        self
+    }
+    /// Set the frequency multiplier of each octave.
+    pub fn with_lacunarity(mut self, lacunarity: f64) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+    /// Sample the noise at a position, never tiles.
+    pub fn sample(&self, x: f64, y: f64) -> f64 {
+        self.sample_periodic(x, y, None, None)
+    }
+    /// Sample every point of the map at its cell center, so separately sampled chunks tile seamlessly.
+    ///
+    /// Cyclic axes tile perfectly, the frequency of each octave is rounded to fit the size of the axis.
+    pub fn fill(&self, map: &mut TaxicabMap<f64>) {
+        let (w, h) = map.get_size();
+        let (cycle_x, cycle_y) = map.get_cycle();
+        let period_x = cycle_x.then_some(w);
+        let period_y = cycle_y.then_some(h);
+        // reduce by the period first, so the same point always gets exactly the same value
+        let reduce = |v: isize, period: Option<usize>| match period {
+            Some(p) if p > 0 => v.rem_euclid(p as isize),
+            _ => v,
+        };
+        for (x, y, v) in map.points_mut() {
+            let (x, y) = (reduce(x, period_x), reduce(y, period_y));
+            *v = self.sample_periodic(x as f64 + 0.5, y as f64 + 0.5, period_x, period_y);
+        }
+    }
+    fn sample_periodic(&self, x: f64, y: f64, period_x: Option<usize>, period_y: Option<usize>) -> f64 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut amplitudes = 0.0;
+        let mut frequency = self.frequency;
+        for octave in 0..self.octaves {
+            let (fx, px) = fit_period(frequency, period_x);
+            let (fy, py) = fit_period(frequency, period_y);
+            let seed = self.seed.wrapping_add(octave as u64);
+            total += amplitude * self.lattice(x * fx, y * fy, px, py, seed);
+            amplitudes += amplitude;
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+        if amplitudes == 0.0 { 0.0 } else { (total / amplitudes).clamp(-1.0, 1.0) }
+    }
+    fn lattice(&self, u: f64, v: f64, px: Option<i64>, py: Option<i64>, seed: u64) -> f64 {
+        let (i, j) = (u.floor() as i64, v.floor() as i64);
+        let (fu, fv) = (u - i as f64, v - j as f64);
+        let corner = |di: i64, dj: i64| {
+            let hash = lattice_hash(wrap(i + di, px), wrap(j + dj, py), seed);
+            match self.kind {
+                NoiseKind::Value => (hash >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0,
+                NoiseKind::Perlin => {
+                    let angle = (hash >> 11) as f64 / (1u64 << 53) as f64 * std::f64::consts::TAU;
+                    // scaled so the result covers about `[-1, 1]`
+                    (angle.cos() * (fu - di as f64) + angle.sin() * (fv - dj as f64)) * std::f64::consts::SQRT_2
+                }
+            }
+        };
+        let (su, sv) = (fade(fu), fade(fv));
+        let bottom = lerp(corner(0, 0), corner(1, 0), su);
+        let top = lerp(corner(0, 1), corner(1, 1), su);
+        lerp(bottom, top, sv)
+    }
+}
+
+fn fit_period(frequency: f64, period: Option<usize>) -> (f64, Option<i64>) {
+    match period {
+        Some(size) if size > 0 => {
+            let cells = (size as f64 * frequency).round().max(1.0);
+            (cells / size as f64, Some(cells as i64))
+        }
+        _ => (frequency, None),
+    }
+}
+
+fn wrap(index: i64, period: Option<i64>) -> i64 {
+    match period {
+        Some(p) => index.rem_euclid(p),
+        None => index,
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// SplitMix64 finalizer over the lattice point and the seed.
+fn lattice_hash(i: i64, j: i64, seed: u64) -> u64 {
+    let mut z = seed ^ (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (j as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,218 @@
+use super::*;
+
+/// Tiles with weights and the adjacency rules between them, used by wave function collapse.
+#[derive(Clone, Debug)]
+pub struct TileSet<T> {
+    tiles: Vec<(T, f64)>,
+    rules: BTreeSet<(usize, Direction, usize)>,
+    backtracks: usize,
+}
+
+impl<T> Default for TileSet<T> {
+    fn default() -> Self {
+        Self { tiles: vec![], rules: BTreeSet::new(), backtracks: 1000 }
+    }
+}
+
+impl<T: Clone + PartialEq> TileSet<T> {
+    /// Learn the tiles, weights and adjacency rules from a sample, wraps of the sample are followed.
+    pub fn learn(sample: &TaxicabMap<T>) -> Self {
+        let mut out = Self::default();
+        for (_, _, v) in sample.points_all() {
+            match out.find(v) {
+                Some(index) => out.tiles[index].1 += 1.0,
+                None => out.tiles.push((v.clone(), 1.0)),
+            }
+        }
+        for (x, y, v) in sample.points_all() {
+            for (joint, (tx, ty)) in sample.steps_nearby(x, y) {
+                if let Some(target) = sample.get_point(tx, ty) {
+                    out.allow(v, joint.get_direction(), target);
+                }
+            }
+        }
+        out
+    }
+    /// Add a tile with the weight, returns false if the tile already exists.
+    pub fn add_tile(&mut self, tile: T, weight: f64) -> bool {
+        if self.find(&tile).is_some() {
+            return false;
+        }
+        self.tiles.push((tile, weight));
+        true
+    }
+    /// Add a tile with the weight.
+    pub fn with_tile(mut self, tile: T, weight: f64) -> Self {
+        self.add_tile(tile, weight);
+        self
+    }
+    /// Allow `b` to be placed at the `direction` side of `a`, the reverse rule is also added.
+    ///
+    /// Returns false if any tile is not in the set.
+    pub fn allow(&mut self, a: &T, direction: Direction, b: &T) -> bool {
+        let (a, b) = match (self.find(a), self.find(b)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return false,
+        };
+        self.rules.insert((a, direction, b));
+        self.rules.insert((b, !direction, a));
+        true
+    }
+    /// Allow `b` to be placed at the `direction` side of `a`, the reverse rule is also added.
+    pub fn with_rule(mut self, a: &T, direction: Direction, b: &T) -> Self {
+        self.allow(a, direction, b);
+        self
+    }
+    /// Set the maximum number of backtracks before giving up.
+    pub fn with_backtracks(mut self, limit: usize) -> Self {
+        self.backtracks = limit;
+        self
+    }
+    fn find(&self, tile: &T) -> Option<usize> {
+        self.tiles.iter().position(|(t, _)| t == tile)
+    }
+    /// Fill all `None` points of the target with tiles, the `Some` points are kept as pre-placed tiles.
+    ///
+    /// Cycles, twists, holes and portals of the target are followed. Returns false if no solution is found,
+    /// the target is not changed in that case.
+    pub fn collapse<R: Rng + ?Sized>(&self, target: &mut TaxicabMap<Option<T>>, rng: &mut R) -> bool {
+        let mut wave = Wave::new(self, target);
+        for (cell, (x, y)) in wave.points.clone().into_iter().enumerate() {
+            if let Some(Some(tile)) = target.get_point(x, y) {
+                match self.find(tile) {
+                    Some(tile) => wave.decide(cell, tile),
+                    None => return false,
+                }
+            }
+        }
+        if !wave.propagate((0..wave.points.len()).collect()) {
+            return false;
+        }
+        // pre-placed tiles are never undone
+        wave.trail.clear();
+        // each decision keeps the length of the trail before it
+        let mut stack: Vec<(usize, usize, usize)> = vec![];
+        let mut backtracks = 0;
+        while let Some(cell) = wave.lowest_entropy(rng) {
+            let tile = wave.choose_tile(cell, rng);
+            stack.push((wave.trail.len(), cell, tile));
+            wave.decide(cell, tile);
+            let mut consistent = wave.propagate(vec![cell]);
+            while !consistent {
+                backtracks += 1;
+                let (mark, cell, tile) = match stack.pop() {
+                    Some(s) if backtracks <= self.backtracks => s,
+                    _ => return false,
+                };
+                wave.rollback(mark);
+                wave.ban(cell, tile);
+                consistent = wave.domains[cell].contains(&true) && wave.propagate(vec![cell]);
+            }
+        }
+        for (cell, (x, y)) in wave.points.iter().enumerate() {
+            let tile = wave.domains[cell].iter().position(|v| *v).map(|t| self.tiles[t].0.clone());
+            if let Some(v) = target.mut_point(*x, *y) {
+                *v = tile;
+            }
+        }
+        true
+    }
+}
+
+struct Wave<'a, T> {
+    tiles: &'a TileSet<T>,
+    points: Vec<(isize, isize)>,
+    neighbors: Vec<Vec<(Direction, usize)>>,
+    domains: Vec<Vec<bool>>,
+    /// Removed `(cell, tile)` pairs in order, undone by truncating
+    trail: Vec<(usize, usize)>,
+}
+
+impl<'a, T> Wave<'a, T> {
+    fn new<U>(tiles: &'a TileSet<T>, target: &TaxicabMap<U>) -> Self {
+        let points = target.points_all().map(|(x, y, _)| (x, y)).collect::<Vec<_>>();
+        let index = points.iter().enumerate().map(|(i, p)| (*p, i)).collect::<BTreeMap<_, _>>();
+        let neighbors = points
+            .iter()
+            .map(|(x, y)| {
+                target.steps_nearby(*x, *y).into_iter().map(|(joint, p)| (joint.get_direction(), index[&p])).collect()
+            })
+            .collect();
+        let domains = vec![vec![true; tiles.tiles.len()]; points.len()];
+        Self { tiles, points, neighbors, domains, trail: vec![] }
+    }
+    fn ban(&mut self, cell: usize, tile: usize) {
+        if self.domains[cell][tile] {
+            self.domains[cell][tile] = false;
+            self.trail.push((cell, tile));
+        }
+    }
+    /// Keep only the tile in the cell.
+    fn decide(&mut self, cell: usize, tile: usize) {
+        for t in 0..self.tiles.tiles.len() {
+            if t != tile {
+                self.ban(cell, t)
+            }
+        }
+    }
+    /// Restore all tiles removed after the trail had the given length.
+    fn rollback(&mut self, mark: usize) {
+        for (cell, tile) in self.trail.drain(mark..) {
+            self.domains[cell][tile] = true;
+        }
+    }
+    /// Remove tiles that have no support from the changed cells, returns false on contradiction.
+    fn propagate(&mut self, mut queue: Vec<usize>) -> bool {
+        let n = self.tiles.tiles.len();
+        while let Some(cell) = queue.pop() {
+            for &(direction, other) in self.neighbors[cell].iter() {
+                let mut changed = false;
+                for b in 0..n {
+                    if !self.domains[other][b] {
+                        continue;
+                    }
+                    let supported = (0..n).any(|a| self.domains[cell][a] && self.tiles.rules.contains(&(a, direction, b)));
+                    if !supported {
+                        self.domains[other][b] = false;
+                        self.trail.push((other, b));
+                        changed = true;
+                    }
+                }
+                if changed {
+                    if !self.domains[other].contains(&true) {
+                        return false;
+                    }
+                    queue.push(other);
+                }
+            }
+        }
+        true
+    }
+    /// Find the undecided cell with the fewest choices, ties are broken randomly.
+    fn lowest_entropy<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let mut best: Option<(usize, f64, usize)> = None;
+        for (cell, domain) in self.domains.iter().enumerate() {
+            let count = domain.iter().filter(|v| **v).count();
+            if count <= 1 {
+                continue;
+            }
+            let noise = rng.gen::<f64>();
+            if best.is_none_or(|(c, n, _)| (count, noise) < (c, n)) {
+                best = Some((count, noise, cell));
+            }
+        }
+        best.map(|(_, _, cell)| cell)
+    }
+    fn choose_tile<R: Rng + ?Sized>(&self, cell: usize, rng: &mut R) -> usize {
+        let choices = self.domains[cell].iter().enumerate().filter(|(_, v)| **v).map(|(t, _)| t).collect::<Vec<_>>();
+        let total: f64 = choices.iter().map(|t| self.tiles.tiles[*t].1.max(0.0)).sum();
+        let mut roll = rng.gen::<f64>() * total;
+        for t in choices.iter() {
+            roll -= self.tiles.tiles[*t].1.max(0.0);
+            if roll <= 0.0 {
+                return *t;
+            }
+        }
+        choices[choices.len() - 1]
+    }
+}
@@ -0,0 +1,292 @@
+use super::*;
+use crate::Portal;
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind, Read, Result, Write},
+};
+
+const MAGIC: &[u8; 4] = b"TXMP";
+const VERSION: u8 = 1;
+/// Largest number of cells [`TaxicabMap::read_binary`] accepts, and a bounds edit may give.
+pub(crate) const MAX_AREA: usize = 1 << 26;
+
+impl<T> TaxicabMap<T> {
+    /// Write the map in the compact binary format, the function encodes each distinct value once into the palette.
+    ///
+    /// The header holds the magic `TXMP` and the format version, followed by the origin, the cycles and twists,
+    /// the palette, run-length encoded cells, the mask and the portals. An Adler-32 checksum of all bytes ends the stream.
+    pub fn write_binary<W, F>(&self, writer: W, encode: F) -> Result<()>
+    where
+        W: Write,
+        F: Fn(&T) -> Vec<u8>,
+    {
+        let mut out = Checked::new(writer);
+        let mut palette = BTreeMap::new();
+        let mut entries = vec![];
+        let cells = self.map_values(|_, _, v| {
+            let bytes = encode(v);
+            *palette.entry(bytes.clone()).or_insert_with(|| {
+                entries.push(bytes);
+                entries.len() - 1
+            })
+        });
+        let ((cycle_x, cycle_y), (twist_x, twist_y)) = (self.get_cycle(), self.get_twist());
+        let flags = [cycle_x, cycle_y, twist_x, twist_y, self.is_masked()].iter().rev().fold(0u8, |f, b| f << 1 | *b as u8);
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION, flags])?;
+        let (origin_x, origin_y) = self.get_origin();
+        write_signed(&mut out, origin_x)?;
+        write_signed(&mut out, origin_y)?;
+        let (w, h) = self.get_size();
+        write_varint(&mut out, w as u64)?;
+        write_varint(&mut out, h as u64)?;
+        write_varint(&mut out, entries.len() as u64)?;
+        for entry in &entries {
+            write_varint(&mut out, entry.len() as u64)?;
+            out.write_all(entry)?;
+        }
+        let indexes = (0..w).flat_map(|i| (0..h).map(move |j| (i, j)));
+        for (index, run) in runs(indexes.clone().map(|(i, j)| cells.get_relative_value(i, j))) {
+            write_varint(&mut out, run)?;
+            write_varint(&mut out, index as u64)?;
+        }
+        if self.is_masked() {
+            // runs alternate between existing and missing points, starting with existing
+            let mut exists = true;
+            for (value, run) in runs(indexes.map(|(i, j)| self.has_relative(i, j))) {
+                if value != exists {
+                    write_varint(&mut out, 0)?;
+                }
+                write_varint(&mut out, run)?;
+                exists = !value;
+            }
+        }
+        let portals = self.portals().collect::<Vec<_>>();
+        write_varint(&mut out, portals.len() as u64)?;
+        for (entrance, portal) in portals {
+            write_joint(&mut out, entrance)?;
+            write_joint(&mut out, portal.get_target())?;
+            match portal.get_cost() {
+                Some(cost) => {
+                    out.write_all(&[1])?;
+                    out.write_all(&cost.to_le_bytes())?;
+                }
+                None => out.write_all(&[0])?,
+            }
+        }
+        let checksum = out.checksum();
+        out.inner.write_all(&checksum.to_le_bytes())
+    }
+    /// Read a map written by [`TaxicabMap::write_binary`], the function decodes each palette entry.
+    ///
+    /// Fails with [`ErrorKind::InvalidData`] on a wrong magic, an unknown version, a rejected value or a bad checksum.
+    /// Maps of more than 2<sup>26</sup> cells are rejected, use [`TaxicabMap::read_binary_limited`] to choose the limit.
+    pub fn read_binary<R, F>(reader: R, decode: F) -> Result<Self>
+    where
+        R: Read,
+        T: Clone,
+        F: Fn(&[u8]) -> Option<T>,
+    {
+        Self::read_binary_limited(reader, MAX_AREA, decode)
+    }
+    /// Read a map written by [`TaxicabMap::write_binary`] with at most `max_area` cells, see [`TaxicabMap::read_binary`].
+    ///
+    /// The cells are allocated before the checksum is checked, so a few bytes may allocate up to `max_area` values.
+    pub fn read_binary_limited<R, F>(reader: R, max_area: usize, decode: F) -> Result<Self>
+    where
+        R: Read,
+        T: Clone,
+        F: Fn(&[u8]) -> Option<T>,
+    {
+        let mut input = Checked::new(reader);
+        let mut header = [0; 6];
+        input.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a taxicab map"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid("unknown format version"));
+        }
+        let flag = |n: u8| header[5] >> n & 1 == 1;
+        let origin = (read_signed(&mut input)?, read_signed(&mut input)?);
+        let (w, h) = (read_varint(&mut input)? as usize, read_varint(&mut input)? as usize);
+        let area = w.checked_mul(h).filter(|area| *area <= max_area).ok_or_else(|| invalid("map too large"))?;
+        let mut palette = vec![];
+        for _ in 0..read_varint(&mut input)? {
+            let len = read_varint(&mut input)?;
+            let mut bytes = vec![];
+            (&mut input).take(len).read_to_end(&mut bytes)?;
+            if bytes.len() as u64 != len {
+                return Err(ErrorKind::UnexpectedEof.into());
+            }
+            palette.push(decode(&bytes).ok_or_else(|| invalid("rejected palette entry"))?);
+        }
+        // cells grow run by run, but one run may fill the whole area before the checksum is read
+        let mut cells = vec![];
+        while cells.len() < area {
+            let run = read_varint(&mut input)? as usize;
+            let value = palette.get(read_varint(&mut input)? as usize).ok_or_else(|| invalid("palette index out of range"))?;
+            if run == 0 || run > area - cells.len() {
+                return Err(invalid("bad run length"));
+            }
+            cells.extend(std::iter::repeat_n(value, run).cloned());
+        }
+        let dense = ndarray::Array2::from_shape_vec((w, h), cells).map_err(|_| invalid("bad shape"))?;
+        let mut map = TaxicabMap::rectangle(w, h, &()).with_dense(dense).with_origin(origin.0, origin.1);
+        if flag(4) {
+            let (mut done, mut exists) = (0, true);
+            while done < area {
+                let run = read_varint(&mut input)? as usize;
+                if run > area - done {
+                    return Err(invalid("bad mask run length"));
+                }
+                for k in done..done + run {
+                    map.set_point_exists(origin.0 + (k / h) as isize, origin.1 + (k % h) as isize, exists);
+                }
+                done += run;
+                exists = !exists;
+            }
+        }
+        map.set_cycle(flag(0), flag(1));
+        map.set_twist(flag(2), flag(3));
+        for _ in 0..read_varint(&mut input)? {
+            let entrance = read_joint(&mut input)?;
+            let mut portal = Portal::new(read_joint(&mut input)?);
+            let mut has_cost = [0];
+            input.read_exact(&mut has_cost)?;
+            if has_cost[0] == 1 {
+                let mut cost = [0; 8];
+                input.read_exact(&mut cost)?;
+                portal = portal.with_cost(f64::from_le_bytes(cost));
+            }
+            if !map.add_portal(entrance, portal) {
+                return Err(invalid("portal out of the map"));
+            }
+        }
+        let checksum = input.checksum();
+        let mut expected = [0; 4];
+        input.inner.read_exact(&mut expected)?;
+        if u32::from_le_bytes(expected) != checksum {
+            return Err(invalid("checksum mismatch"));
+        }
+        Ok(map)
+    }
+}
+
+/// Adler-32 over all bytes passing through.
+struct Checked<S> {
+    inner: S,
+    a: u32,
+    b: u32,
+}
+
+impl<S> Checked<S> {
+    fn new(inner: S) -> Self {
+        Self { inner, a: 1, b: 0 }
+    }
+    fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.a = (self.a + *byte as u32) % 65521;
+            self.b = (self.b + self.a) % 65521;
+        }
+    }
+    fn checksum(&self) -> u32 {
+        self.b << 16 | self.a
+    }
+}
+
+impl<W: Write> Write for Checked<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: Read> Read for Checked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+pub(crate) fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Group equal neighbors into `(value, run length)`.
+fn runs<V: PartialEq, I: Iterator<Item = V>>(values: I) -> Vec<(V, u64)> {
+    let mut out: Vec<(V, u64)> = vec![];
+    for v in values {
+        match out.last_mut() {
+            Some((last, run)) if *last == v => *run += 1,
+            _ => out.push((v, 1)),
+        }
+    }
+    out
+}
+
+pub(crate) fn write_varint<W: Write>(out: &mut W, mut v: u64) -> Result<()> {
+    loop {
+        let byte = (v & 0x7F) as u8;
+        v >>= 7;
+        if v == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+pub(crate) fn read_varint<R: Read>(input: &mut R) -> Result<u64> {
+    let mut v = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        input.read_exact(&mut byte)?;
+        v |= ((byte[0] & 0x7F) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+pub(crate) fn write_signed<W: Write>(out: &mut W, v: isize) -> Result<()> {
+    let v = v as i64;
+    write_varint(out, ((v << 1) ^ (v >> 63)) as u64)
+}
+
+pub(crate) fn read_signed<R: Read>(input: &mut R) -> Result<isize> {
+    let v = read_varint(input)?;
+    Ok(((v >> 1) as i64 ^ -((v & 1) as i64)) as isize)
+}
+
+fn write_joint<W: Write>(out: &mut W, joint: Joint) -> Result<()> {
+    let (x, y) = joint.source();
+    write_signed(out, x)?;
+    write_signed(out, y)?;
+    let direction = match joint.get_direction() {
+        Direction::X(true) => 0,
+        Direction::X(false) => 1,
+        Direction::Y(true) => 2,
+        Direction::Y(false) => 3,
+    };
+    out.write_all(&[direction])
+}
+
+fn read_joint<R: Read>(input: &mut R) -> Result<Joint> {
+    let (x, y) = (read_signed(input)?, read_signed(input)?);
+    let mut direction = [0];
+    input.read_exact(&mut direction)?;
+    let direction = match direction[0] {
+        0 => Direction::X(true),
+        1 => Direction::X(false),
+        2 => Direction::Y(true),
+        3 => Direction::Y(false),
+        _ => return Err(invalid("bad direction")),
+    };
+    Ok(Joint::new(x, y, direction))
+}
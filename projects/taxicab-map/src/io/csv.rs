@@ -0,0 +1,132 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// Options of the CSV format, values must not contain the delimiter or line breaks.
+///
+/// Holes are written as empty fields, and empty fields are read as holes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CsvFormat {
+    delimiter: char,
+    reverse: bool,
+    header: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self { delimiter: ',', reverse: false, header: false }
+    }
+}
+
+impl CsvFormat {
+    /// Set the delimiter between fields.
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+    /// Set whether the first line is the bottom row, like [`TaxicabMap::rows`].
+    pub fn with_reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+    /// Set whether a header row and column hold the absolute coordinates, so the origin round-trips.
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+impl<T: Display> TaxicabMap<T> {
+    /// Write the map as CSV, one line per row.
+    pub fn to_csv(&self, format: &CsvFormat) -> String {
+        let (x_range, y_range) = self.get_range();
+        let d = format.delimiter.to_string();
+        let mut out = String::new();
+        if format.header {
+            out.push_str(&x_range.clone().map(|x| format!("{d}{x}")).collect::<String>());
+            out.push('\n');
+        }
+        let rows: Box<dyn Iterator<Item = isize>> = match format.reverse {
+            true => Box::new(y_range),
+            false => Box::new(y_range.rev()),
+        };
+        for y in rows {
+            let mut fields =
+                x_range.clone().map(|x| self.get_point(x, y).map(|v| v.to_string()).unwrap_or_default()).collect::<Vec<_>>();
+            if format.header {
+                fields.insert(0, y.to_string());
+            }
+            out.push_str(&fields.join(&d));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl<T: FromStr> TaxicabMap<T> {
+    /// Read a map from CSV, the origin comes from the header or is `(0, 0)` without one.
+    ///
+    /// Blank lines at the end are ignored, so a map one point wide loses the rows of holes at its end.
+    /// Returns `None` if the lines are ragged, a value fails to parse, the coordinates are not consecutive,
+    /// or there is no value at all.
+    pub fn from_csv(text: &str, format: &CsvFormat) -> Option<Self>
+    where
+        T: Clone,
+    {
+        // an empty line is a row of holes when the map is one point wide, only blank lines at the end are skipped
+        let mut lines = text.lines().collect::<Vec<_>>();
+        while lines.last().is_some_and(|line| line.chars().all(|c| c.is_whitespace() && c != format.delimiter)) {
+            lines.pop();
+        }
+        let mut lines = lines.into_iter().map(|line| line.split(format.delimiter).map(str::trim));
+        let (mut origin_x, mut columns) = (0, None);
+        if format.header {
+            let xs = lines.next()?.skip(1).map(|x| x.parse::<isize>().ok()).collect::<Option<Vec<_>>>()?;
+            origin_x = *xs.first()?;
+            if xs.iter().zip(origin_x..).any(|(x, expect)| *x != expect) {
+                return None;
+            }
+            columns = Some(xs.len());
+        }
+        let mut ys = vec![];
+        let mut rows = vec![];
+        for mut fields in lines {
+            if format.header {
+                ys.push(fields.next()?.parse::<isize>().ok()?);
+            }
+            // an empty field is a hole
+            let row = fields
+                .map(|f| if f.is_empty() { Some(None) } else { f.parse::<T>().ok().map(Some) })
+                .collect::<Option<Vec<_>>>()?;
+            rows.push(row);
+        }
+        if !format.reverse {
+            rows.reverse();
+            ys.reverse();
+        }
+        let origin_y = ys.first().copied().unwrap_or(0);
+        if ys.iter().zip(origin_y..).any(|(y, expect)| *y != expect) {
+            return None;
+        }
+        let width = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != width) || columns.is_some_and(|c| c != width) {
+            return None;
+        }
+        let fill = rows.iter().flatten().flatten().next().cloned();
+        let mut map = TaxicabMap::rectangle(width, rows.len(), &fill?).with_origin(origin_x, origin_y);
+        for (j, row) in rows.into_iter().enumerate() {
+            for (i, value) in row.into_iter().enumerate() {
+                let (x, y) = (origin_x + i as isize, origin_y + j as isize);
+                match value {
+                    Some(v) => {
+                        map.set_point(x, y, v);
+                    }
+                    None => {
+                        map.set_point_exists(x, y, false);
+                    }
+                }
+            }
+        }
+        Some(map)
+    }
+}
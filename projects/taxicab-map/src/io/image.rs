@@ -0,0 +1,203 @@
+use super::*;
+use ndarray::ShapeBuilder;
+use std::collections::BTreeMap;
+
+/// Render a map as an RGB image, the highest row on top and each point drawn as a square of pixels.
+///
+/// Created by [`TaxicabMap::render_image`].
+pub struct ImageRender<'a, T, F> {
+    map: &'a TaxicabMap<T>,
+    color: F,
+    hole: [u8; 3],
+    scale: usize,
+    paths: BTreeMap<(isize, isize), (Direction, [u8; 3])>,
+    origin: Option<[u8; 3]>,
+}
+
+impl<T> TaxicabMap<T> {
+    /// Create an image renderer, the function picks the color of each point.
+    pub fn render_image<F>(&self, color: F) -> ImageRender<'_, T, F>
+    where
+        F: Fn(isize, isize, &T) -> [u8; 3],
+    {
+        ImageRender { map: self, color, hole: [0; 3], scale: 1, paths: BTreeMap::new(), origin: None }
+    }
+    /// Load a map from a binary PPM (`P6`) or PGM (`P5`) image, the bottom left pixel is `(0, 0)`.
+    ///
+    /// Gray pixels are given as three equal channels, returns `None` if the image is malformed or a color is rejected.
+    pub fn from_ppm<F>(bytes: &[u8], value: F) -> Option<Self>
+    where
+        F: Fn([u8; 3]) -> Option<T>,
+    {
+        let mut rest = bytes;
+        let magic = next_token(&mut rest)?;
+        let channels = match magic {
+            b"P5" => 1,
+            b"P6" => 3,
+            _ => return None,
+        };
+        let mut number = || std::str::from_utf8(next_token(&mut rest)?).ok()?.parse::<usize>().ok();
+        let (width, height, max) = (number()?, number()?, number()?);
+        if max == 0 || max > 255 {
+            return None;
+        }
+        // exactly one whitespace separates the header from the raster
+        let raster = rest.get(1..)?.get(..width.checked_mul(height)?.checked_mul(channels)?)?;
+        let pixels = raster.chunks(channels).map(|c| if channels == 1 { [c[0]; 3] } else { [c[0], c[1], c[2]] });
+        from_pixels(width, height, pixels, value)
+    }
+    /// Load a map from a PNG image, the bottom left pixel is `(0, 0)`.
+    ///
+    /// Alpha is dropped and gray pixels are given as three equal channels.
+    #[cfg(feature = "png")]
+    pub fn from_png<F>(bytes: &[u8], value: F) -> Option<Self>
+    where
+        F: Fn([u8; 3]) -> Option<T>,
+    {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().ok()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).ok()?;
+        let channels = info.color_type.samples();
+        let raster = buffer.get(..(info.width as usize).checked_mul(info.height as usize)?.checked_mul(channels)?)?;
+        let pixels = raster.chunks(channels).map(|c| if channels < 3 { [c[0]; 3] } else { [c[0], c[1], c[2]] });
+        from_pixels(info.width as usize, info.height as usize, pixels, value)
+    }
+}
+
+fn next_token<'b>(rest: &mut &'b [u8]) -> Option<&'b [u8]> {
+    loop {
+        match rest.first()? {
+            b'#' => *rest = &rest[rest.iter().position(|c| *c == b'\n')?..],
+            c if c.is_ascii_whitespace() => *rest = &rest[1..],
+            _ => break,
+        }
+    }
+    let end = rest.iter().position(|c| c.is_ascii_whitespace()).unwrap_or(rest.len());
+    let (token, tail) = rest.split_at(end);
+    *rest = tail;
+    Some(token)
+}
+
+fn from_pixels<T, I, F>(width: usize, height: usize, pixels: I, value: F) -> Option<TaxicabMap<T>>
+where
+    I: Iterator<Item = [u8; 3]>,
+    F: Fn([u8; 3]) -> Option<T>,
+{
+    if width == 0 || height == 0 {
+        let dense = ndarray::Array2::from_shape_vec((width, height), vec![]).ok()?;
+        return Some(TaxicabMap::rectangle(width, height, &()).with_dense(dense));
+    }
+    // the caller checked that the raster holds `width * height` pixels, so the height is bounded by the input
+    let mut rows = Vec::with_capacity(height);
+    let mut pixels = pixels.map(value);
+    for _ in 0..height {
+        rows.push(pixels.by_ref().take(width).collect::<Option<Vec<_>>>()?);
+    }
+    if rows.iter().any(|row| row.len() != width) {
+        return None;
+    }
+    // pixel rows go top down, in column major order the bottom up rows are indexed by (x, y)
+    let values = rows.into_iter().rev().flatten().collect();
+    let dense = ndarray::Array2::from_shape_vec((width, height).f(), values).ok()?;
+    Some(TaxicabMap::rectangle(width, height, &()).with_dense(dense))
+}
+
+impl<'a, T, F> ImageRender<'a, T, F> {
+    /// Set the color drawn for holes in the map.
+    pub fn with_hole(mut self, hole: [u8; 3]) -> Self {
+        self.hole = hole;
+        self
+    }
+    /// Set the size in pixels of each point.
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+    /// Overlay a path, each joint draws a line from the center of its source point towards its direction.
+    pub fn with_path(mut self, path: &[Joint], color: [u8; 3]) -> Self {
+        for joint in path {
+            if let Some(joint) = self.map.wrap_joint(*joint) {
+                self.paths.insert(joint.source(), (joint.get_direction(), color));
+            }
+        }
+        self
+    }
+    /// Mark the origin point with a frame, or fill it when the scale is too small.
+    pub fn with_origin_marker(mut self, color: [u8; 3]) -> Self {
+        self.origin = Some(color);
+        self
+    }
+}
+
+impl<'a, T, F> ImageRender<'a, T, F>
+where
+    F: Fn(isize, isize, &T) -> [u8; 3],
+{
+    /// Get the width, height and the pixels row by row from the top.
+    pub fn pixels(&self) -> (usize, usize, Vec<[u8; 3]>) {
+        let (x_range, y_range) = self.map.get_range();
+        let s = self.scale;
+        let (width, height) = (x_range.len() * s, y_range.len() * s);
+        let mut pixels = vec![self.hole; width * height];
+        for (i, x) in x_range.clone().enumerate() {
+            for (j, y) in y_range.clone().rev().enumerate() {
+                let mut cell = |px: usize, py: usize, color: [u8; 3]| pixels[(j * s + py) * width + i * s + px] = color;
+                let fill = match self.map.get_point(x, y) {
+                    Some(v) => (self.color)(x, y, v),
+                    None => self.hole,
+                };
+                for (px, py) in (0..s).flat_map(|px| (0..s).map(move |py| (px, py))) {
+                    let frame = px == 0 || py == 0 || px == s - 1 || py == s - 1;
+                    let color = match self.origin {
+                        Some(marker) if (x, y) == (0, 0) && (s < 3 || frame) => marker,
+                        _ => fill,
+                    };
+                    cell(px, py, color)
+                }
+                if let Some((direction, color)) = self.paths.get(&(x, y)) {
+                    let c = s / 2;
+                    let line: Box<dyn Iterator<Item = (usize, usize)>> = match direction {
+                        Direction::X(true) => Box::new((c..s).map(|px| (px, c))),
+                        Direction::X(false) => Box::new((0..=c).map(|px| (px, c))),
+                        Direction::Y(true) => Box::new((0..=c).map(|py| (c, py))),
+                        Direction::Y(false) => Box::new((c..s).map(|py| (c, py))),
+                    };
+                    line.for_each(|(px, py)| cell(px, py, *color));
+                }
+            }
+        }
+        (width, height, pixels)
+    }
+    /// Encode as a binary PPM (`P6`) image.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let (width, height, pixels) = self.pixels();
+        let mut out = format!("P6\n{width} {height}\n255\n").into_bytes();
+        out.extend(pixels.into_iter().flatten());
+        out
+    }
+    /// Encode as a binary PGM (`P5`) image, colors are converted to luma.
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let (width, height, pixels) = self.pixels();
+        let mut out = format!("P5\n{width} {height}\n255\n").into_bytes();
+        out.extend(pixels.into_iter().map(|[r, g, b]| ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8));
+        out
+    }
+    /// Encode as a PNG image, fails if the image is empty or too large for PNG.
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> std::io::Result<Vec<u8>> {
+        let (width, height, pixels) = self.pixels();
+        let too_large = || std::io::Error::new(std::io::ErrorKind::InvalidInput, "image too large for png");
+        let (width, height) = (u32::try_from(width).map_err(|_| too_large())?, u32::try_from(height).map_err(|_| too_large())?);
+        let mut out = vec![];
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let data = pixels.into_iter().flatten().collect::<Vec<_>>();
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(out)
+    }
+}
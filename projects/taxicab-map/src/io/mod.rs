@@ -0,0 +1,8 @@
+use crate::{Direction, Joint, TaxicabMap};
+
+pub mod binary;
+pub mod csv;
+pub mod image;
+pub mod text;
+#[cfg(feature = "tiled")]
+pub mod tiled;
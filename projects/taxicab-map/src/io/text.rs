@@ -0,0 +1,112 @@
+use super::*;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Write},
+};
+
+/// Render a map as a character grid, the highest row first.
+///
+/// Created by [`TaxicabMap::render_text`], printed through [`Display`].
+pub struct TextRender<'a, T, F> {
+    map: &'a TaxicabMap<T>,
+    cell: F,
+    hole: char,
+    axes: bool,
+    paths: BTreeMap<(isize, isize), Direction>,
+}
+
+impl<T> TaxicabMap<T> {
+    /// Create a text renderer, the function picks the character of each point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use taxicab_map::TaxicabMap;
+    /// let map = TaxicabMap::rectangle(3, 2, &true);
+    /// assert_eq!(map.render_text(|_, _, v| if *v { '.' } else { '#' }).to_string(), "...\n...\n");
+    /// ```
+    pub fn render_text<F>(&self, cell: F) -> TextRender<'_, T, F>
+    where
+        F: Fn(isize, isize, &T) -> char,
+    {
+        TextRender { map: self, cell, hole: ' ', axes: false, paths: BTreeMap::new() }
+    }
+    /// Parse a map from lines of characters, the last line is `y = 0` and the first column is `x = 0`.
+    ///
+    /// Blank lines around the grid are ignored, returns `None` if the lines are ragged or a character is rejected.
+    pub fn parse_text<F>(text: &str, value: F) -> Option<Self>
+    where
+        F: Fn(char) -> Option<T>,
+    {
+        let lines = text.lines().map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+        let blank = |line: &Vec<char>| line.iter().all(|c| c.is_whitespace());
+        let start = lines.iter().position(|line| !blank(line)).unwrap_or(lines.len());
+        let end = lines.iter().rposition(|line| !blank(line)).map_or(start, |i| i + 1);
+        let lines = &lines[start..end];
+        let width = lines.first().map_or(0, |line| line.len());
+        if lines.iter().any(|line| line.len() != width) {
+            return None;
+        }
+        let mut values = Vec::with_capacity(width * lines.len());
+        for x in 0..width {
+            for line in lines.iter().rev() {
+                values.push(value(line[x])?);
+            }
+        }
+        let dense = ndarray::Array2::from_shape_vec((width, lines.len()), values).ok()?;
+        Some(TaxicabMap::rectangle(width, lines.len(), &()).with_dense(dense))
+    }
+}
+
+impl<'a, T, F> TextRender<'a, T, F> {
+    /// Set the character drawn for holes in the map.
+    pub fn with_hole(mut self, hole: char) -> Self {
+        self.hole = hole;
+        self
+    }
+    /// Draw the coordinates around the grid, the header holds the last digit of each x coordinate.
+    pub fn with_axes(mut self, axes: bool) -> Self {
+        self.axes = axes;
+        self
+    }
+    /// Overlay a path, each joint draws its direction arrow at its source point.
+    pub fn with_path(mut self, path: &[Joint]) -> Self {
+        for joint in path {
+            if let Some(joint) = self.map.wrap_joint(*joint) {
+                self.paths.insert(joint.source(), joint.get_direction());
+            }
+        }
+        self
+    }
+}
+
+impl<'a, T, F> Display for TextRender<'a, T, F>
+where
+    F: Fn(isize, isize, &T) -> char,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (x_range, y_range) = self.map.get_range();
+        let label = y_range.clone().map(|y| y.to_string().len()).max().unwrap_or(0);
+        if self.axes {
+            write!(f, "{:label$} ", "")?;
+            for x in x_range.clone() {
+                f.write_char(char::from_digit((x.unsigned_abs() % 10) as u32, 10).unwrap_or('?'))?;
+            }
+            f.write_char('\n')?;
+        }
+        for y in y_range.rev() {
+            if self.axes {
+                write!(f, "{y:>label$} ")?;
+            }
+            for x in x_range.clone() {
+                match (self.paths.get(&(x, y)), self.map.get_point(x, y)) {
+                    (Some(direction), _) => write!(f, "{direction}")?,
+                    (None, Some(v)) => f.write_char((self.cell)(x, y, v))?,
+                    (None, None) => f.write_char(self.hole)?,
+                }
+            }
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
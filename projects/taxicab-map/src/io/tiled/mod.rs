@@ -0,0 +1,449 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+mod tmx;
+
+/// Size of the chunks written for infinite maps, Tiled expects chunk offsets aligned to it.
+const CHUNK: isize = 16;
+/// Chunks may be spread over this many times their own area before they are rejected.
+const SPREAD: usize = 64;
+/// Chunks may always be spread over this many tiles.
+const SPREAD_MIN: usize = 1 << 20;
+
+/// Tilesets, tile layers and object layers of a Tiled map, in the JSON format (`.tmj`) or the XML format (`.tmx`).
+///
+/// Tile row `ty` of Tiled becomes `y = -ty`, so the tile at `(0, 0)` keeps its place and rows go downwards.
+/// Object coordinates are in tiles, with the tile `(x, y)` covering `x..x + 1` and `y..y + 1`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TiledMap {
+    tile_width: u32,
+    tile_height: u32,
+    tilesets: Vec<TiledTileset>,
+    layers: Vec<(String, TaxicabMap<u32>)>,
+    objects: Vec<(String, Vec<TiledObject>)>,
+}
+
+/// A tileset of a Tiled map, the global tile ids from `first_gid` on belong to it.
+///
+/// Properties, animations and the images of single tiles are not kept.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TiledTileset {
+    /// A tileset in its own file
+    External {
+        /// First global tile id
+        first_gid: u32,
+        /// Path of the tileset file
+        source: String,
+    },
+    /// A tileset stored in the map
+    Embedded {
+        /// First global tile id
+        first_gid: u32,
+        /// Name of the tileset
+        name: String,
+        /// Tile width in pixels
+        tile_width: u32,
+        /// Tile height in pixels
+        tile_height: u32,
+        /// Number of tiles
+        tile_count: u32,
+        /// Number of tile columns in the image
+        columns: u32,
+        /// Pixels around the tiles in the image
+        margin: u32,
+        /// Pixels between the tiles in the image
+        spacing: u32,
+        /// Path of the image, `None` for a collection of images
+        image: Option<String>,
+        /// Image width in pixels
+        image_width: u32,
+        /// Image height in pixels
+        image_height: u32,
+    },
+}
+
+/// An object on a Tiled object layer, in tile units.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TiledObject {
+    /// A point object
+    Point {
+        /// Name of the object
+        name: String,
+        /// Horizontal position
+        x: f64,
+        /// Vertical position, upwards
+        y: f64,
+    },
+    /// A rectangle object, from its bottom left corner
+    Rect {
+        /// Name of the object
+        name: String,
+        /// Left edge
+        x: f64,
+        /// Bottom edge
+        y: f64,
+        /// Width of the rectangle
+        width: f64,
+        /// Height of the rectangle
+        height: f64,
+    },
+}
+
+impl TiledMap {
+    /// Create an empty map with the tile size in pixels.
+    pub fn new(tile_width: u32, tile_height: u32) -> Self {
+        Self { tile_width, tile_height, tilesets: vec![], layers: vec![], objects: vec![] }
+    }
+    /// Get the tile size in pixels.
+    pub fn get_tile_size(&self) -> (u32, u32) {
+        (self.tile_width, self.tile_height)
+    }
+    /// Add a tileset, tilesets are written in the order they were added.
+    pub fn add_tileset(&mut self, tileset: TiledTileset) {
+        self.tilesets.push(tileset)
+    }
+    /// Add a tileset, tilesets are written in the order they were added.
+    pub fn with_tileset(mut self, tileset: TiledTileset) -> Self {
+        self.add_tileset(tileset);
+        self
+    }
+    /// Get all tilesets.
+    pub fn tilesets(&self) -> impl Iterator<Item = &TiledTileset> {
+        self.tilesets.iter()
+    }
+    /// Add a tile layer, tile ids are global ids with `0` as the empty tile.
+    pub fn add_layer(&mut self, name: &str, map: TaxicabMap<u32>) {
+        self.layers.push((name.to_string(), map))
+    }
+    /// Add a tile layer, tile ids are global ids with `0` as the empty tile.
+    pub fn with_layer(mut self, name: &str, map: TaxicabMap<u32>) -> Self {
+        self.add_layer(name, map);
+        self
+    }
+    /// Get the first tile layer with the name.
+    pub fn get_layer(&self, name: &str) -> Option<&TaxicabMap<u32>> {
+        self.layers.iter().find(|(n, _)| n == name).map(|(_, map)| map)
+    }
+    /// Get all tile layers in drawing order.
+    pub fn layers(&self) -> impl Iterator<Item = (&str, &TaxicabMap<u32>)> {
+        self.layers.iter().map(|(name, map)| (name.as_str(), map))
+    }
+    /// Add an object layer.
+    pub fn add_objects(&mut self, name: &str, objects: Vec<TiledObject>) {
+        self.objects.push((name.to_string(), objects))
+    }
+    /// Add an object layer.
+    pub fn with_objects(mut self, name: &str, objects: Vec<TiledObject>) -> Self {
+        self.add_objects(name, objects);
+        self
+    }
+    /// Get the objects of the first object layer with the name.
+    pub fn get_objects(&self, name: &str) -> Option<&[TiledObject]> {
+        self.objects.iter().find(|(n, _)| n == name).map(|(_, objects)| objects.as_slice())
+    }
+    /// Get all object layers.
+    pub fn objects(&self) -> impl Iterator<Item = (&str, &[TiledObject])> {
+        self.objects.iter().map(|(name, objects)| (name.as_str(), objects.as_slice()))
+    }
+}
+
+impl TiledTileset {
+    /// Get the first global tile id of the tileset.
+    pub fn get_first_gid(&self) -> u32 {
+        match self {
+            Self::External { first_gid, .. } | Self::Embedded { first_gid, .. } => *first_gid,
+        }
+    }
+}
+
+impl TiledMap {
+    /// Read an orthogonal map, finite or infinite, from the Tiled JSON format.
+    ///
+    /// Groups are flattened, returns `None` for other orientations or encoded layer data.
+    pub fn from_json(text: &str) -> Option<Self> {
+        let root: Value = serde_json::from_str(text).ok()?;
+        if root.get("orientation").and_then(Value::as_str).is_some_and(|o| o != "orthogonal") {
+            return None;
+        }
+        let mut out = Self::new(root["tilewidth"].as_u64()? as u32, root["tileheight"].as_u64()? as u32);
+        for tileset in root.get("tilesets").and_then(Value::as_array).into_iter().flatten() {
+            out.add_tileset(read_tileset(tileset)?);
+        }
+        out.read_layers(root.get("layers")?.as_array()?)?;
+        Some(out)
+    }
+    fn read_layers(&mut self, layers: &[Value]) -> Option<()> {
+        for layer in layers {
+            let name = layer["name"].as_str().unwrap_or_default();
+            match layer["type"].as_str()? {
+                "tilelayer" => {
+                    if layer.get("encoding").is_some_and(|e| e != "csv") {
+                        return None;
+                    }
+                    let map = match layer.get("chunks") {
+                        Some(chunks) => {
+                            let chunks = chunks.as_array()?.iter().map(read_chunk).collect::<Option<Vec<_>>>()?;
+                            merge_chunks(chunks)?
+                        }
+                        None => read_chunk(layer)?,
+                    };
+                    self.add_layer(name, map);
+                }
+                "objectgroup" => {
+                    let objects = layer["objects"].as_array()?.iter().filter_map(|o| self.read_object(o)).collect();
+                    self.add_objects(name, objects);
+                }
+                "group" => self.read_layers(layer["layers"].as_array()?)?,
+                _ => {}
+            }
+        }
+        Some(())
+    }
+    fn read_object(&self, object: &Value) -> Option<TiledObject> {
+        let name = object["name"].as_str().unwrap_or_default().to_string();
+        let (x, y) = (object["x"].as_f64()?, object["y"].as_f64()?);
+        let (width, height) = (object["width"].as_f64().unwrap_or(0.0), object["height"].as_f64().unwrap_or(0.0));
+        Some(self.tile_object(name, [x, y, width, height], object["point"].as_bool() == Some(true)))
+    }
+    /// Convert an object from pixels with `y` downwards, as `[x, y, width, height]`, to tiles.
+    fn tile_object(&self, name: String, pixels: [f64; 4], point: bool) -> TiledObject {
+        let (tw, th) = (self.tile_width as f64, self.tile_height as f64);
+        let [x, y, width, height] = [pixels[0] / tw, pixels[1] / th, pixels[2] / tw, pixels[3] / th];
+        match point {
+            true => TiledObject::Point { name, x, y: 1.0 - y },
+            false => TiledObject::Rect { name, x, y: 1.0 - y - height, width, height },
+        }
+    }
+    /// Convert an object to pixels with `y` downwards, as `[x, y, width, height]`.
+    fn pixel_object(&self, object: &TiledObject) -> [f64; 4] {
+        let (tw, th) = (self.tile_width as f64, self.tile_height as f64);
+        match object {
+            TiledObject::Point { x, y, .. } => [x * tw, (1.0 - y) * th, 0.0, 0.0],
+            TiledObject::Rect { x, y, width, height, .. } => [x * tw, (1.0 - y - height) * th, width * tw, height * th],
+        }
+    }
+}
+
+fn read_tileset(tileset: &Value) -> Option<TiledTileset> {
+    let first_gid = tileset["firstgid"].as_u64()? as u32;
+    if let Some(source) = tileset["source"].as_str() {
+        return Some(TiledTileset::External { first_gid, source: source.to_string() });
+    }
+    let number = |key: &str| tileset[key].as_u64().unwrap_or(0) as u32;
+    Some(TiledTileset::Embedded {
+        first_gid,
+        name: tileset["name"].as_str().unwrap_or_default().to_string(),
+        tile_width: number("tilewidth"),
+        tile_height: number("tileheight"),
+        tile_count: number("tilecount"),
+        columns: number("columns"),
+        margin: number("margin"),
+        spacing: number("spacing"),
+        image: tileset["image"].as_str().map(str::to_string),
+        image_width: number("imagewidth"),
+        image_height: number("imageheight"),
+    })
+}
+
+/// Read a finite layer or a chunk, both have an offset, a size and the data row by row from the top.
+fn read_chunk(chunk: &Value) -> Option<TaxicabMap<u32>> {
+    let (width, height) = (chunk["width"].as_u64()? as usize, chunk["height"].as_u64()? as usize);
+    let (x, y) = (chunk["x"].as_i64().unwrap_or(0) as isize, chunk["y"].as_i64().unwrap_or(0) as isize);
+    let data = chunk["data"].as_array()?.iter().map(|v| v.as_u64().map(|v| v as u32)).collect::<Option<Vec<_>>>()?;
+    chunk_map(x, y, width, height, data)
+}
+
+/// Make the map of a block with its top left tile at `(x, y)` in Tiled coordinates.
+fn chunk_map(x: isize, y: isize, width: usize, height: usize, data: Vec<u32>) -> Option<TaxicabMap<u32>> {
+    // the size is checked against the data before anything is allocated
+    if data.len() != width.checked_mul(height)? {
+        return None;
+    }
+    let bottom = 1isize.checked_sub(y)?.checked_sub(height as isize)?;
+    x.checked_add(width as isize)?;
+    let mut map = TaxicabMap::rectangle(width, height, &0).with_origin(x, bottom);
+    for (i, gid) in data.into_iter().enumerate() {
+        map.set_point(x + (i % width) as isize, -y - (i / width) as isize, gid);
+    }
+    Some(map)
+}
+
+/// Returns `None` if the chunks span an area too large to address, or much larger than the chunks.
+fn merge_chunks(chunks: Vec<TaxicabMap<u32>>) -> Option<TaxicabMap<u32>> {
+    let ranges = chunks.iter().map(|c| c.get_range()).collect::<Vec<_>>();
+    let x_start = ranges.iter().map(|(x, _)| x.start).min().unwrap_or(0);
+    let x_end = ranges.iter().map(|(x, _)| x.end).max().unwrap_or(0);
+    let y_start = ranges.iter().map(|(_, y)| y.start).min().unwrap_or(0);
+    let y_end = ranges.iter().map(|(_, y)| y.end).max().unwrap_or(0);
+    let (width, height) = (x_end.checked_sub(x_start)? as usize, y_end.checked_sub(y_start)? as usize);
+    let total = chunks.iter().map(|c| c.get_size().0 * c.get_size().1).sum::<usize>();
+    if width.checked_mul(height)? > total.saturating_mul(SPREAD).max(SPREAD_MIN) {
+        return None;
+    }
+    let mut map = TaxicabMap::rectangle(width, height, &0).with_origin(x_start, y_start);
+    for chunk in chunks {
+        for (x, y, gid) in chunk.points_all() {
+            map.set_point(x, y, *gid);
+        }
+    }
+    Some(map)
+}
+
+/// A tile layer cut into chunks for an infinite map, offsets and sizes in Tiled coordinates.
+struct Chunks {
+    left: isize,
+    top: isize,
+    width: isize,
+    height: isize,
+    chunks: Vec<(isize, isize, Vec<u32>)>,
+}
+
+impl TiledMap {
+    /// Get the size of the map if it is finite, all layers have the same size with `(0, 0)` on their top left point.
+    fn finite_size(&self) -> Option<(usize, usize)> {
+        let size = self.layers.first().map(|(_, map)| map.get_size()).unwrap_or_default();
+        let finite = self.layers.iter().all(|(_, map)| {
+            let (x, y) = map.get_range();
+            map.get_size() == size && x.start == 0 && y.end == 1
+        });
+        finite.then_some(size)
+    }
+    /// Write the map in the Tiled JSON format.
+    ///
+    /// The map is finite when all layers have the same size with `(0, 0)` on their top left point,
+    /// otherwise it is infinite and the layers are split into chunks. Holes are written as empty tiles.
+    pub fn to_json(&self) -> String {
+        let finite = self.finite_size();
+        let mut layers = vec![];
+        let mut id = 0;
+        let (mut width, mut height) = finite.unwrap_or_default();
+        for (name, map) in &self.layers {
+            id += 1;
+            let mut layer = json!({
+                "id": id, "name": name, "type": "tilelayer", "visible": true, "opacity": 1, "x": 0, "y": 0,
+            });
+            match finite {
+                Some(size) => {
+                    layer["width"] = json!(size.0);
+                    layer["height"] = json!(size.1);
+                    layer["data"] = json!(tiles(map, 0, 0, size.0, size.1));
+                }
+                None => {
+                    let chunks = split_chunks(map);
+                    width = width.max(chunks.width as usize);
+                    height = height.max(chunks.height as usize);
+                    let data = chunks
+                        .chunks
+                        .iter()
+                        .map(|(cx, cy, data)| json!({ "x": cx, "y": cy, "width": CHUNK, "height": CHUNK, "data": data }));
+                    layer["startx"] = json!(chunks.left);
+                    layer["starty"] = json!(chunks.top);
+                    layer["width"] = json!(chunks.width);
+                    layer["height"] = json!(chunks.height);
+                    layer["chunks"] = json!(data.collect::<Vec<_>>());
+                }
+            }
+            layers.push(layer);
+        }
+        let mut object = 0;
+        for (name, objects) in &self.objects {
+            id += 1;
+            let objects = objects
+                .iter()
+                .map(|o| {
+                    object += 1;
+                    let [x, y, w, h] = self.pixel_object(o);
+                    let mut out = json!({
+                        "id": object, "name": o.get_name(), "type": "", "visible": true, "rotation": 0,
+                        "x": x, "y": y, "width": w, "height": h,
+                    });
+                    if let TiledObject::Point { .. } = o {
+                        out["point"] = json!(true);
+                    }
+                    out
+                })
+                .collect::<Vec<_>>();
+            layers.push(json!({
+                "id": id, "name": name, "type": "objectgroup", "visible": true, "opacity": 1, "x": 0, "y": 0,
+                "draworder": "topdown", "objects": objects,
+            }));
+        }
+        let tilesets = self.tilesets.iter().map(write_tileset).collect::<Vec<_>>();
+        json!({
+            "type": "map", "version": "1.10", "orientation": "orthogonal", "renderorder": "right-down",
+            "width": width, "height": height, "tilewidth": self.tile_width, "tileheight": self.tile_height,
+            "infinite": finite.is_none(), "compressionlevel": -1, "nextlayerid": id + 1, "nextobjectid": object + 1,
+            "layers": layers, "tilesets": tilesets,
+        })
+        .to_string()
+    }
+}
+
+impl TiledObject {
+    /// Get the name of the object.
+    pub fn get_name(&self) -> &str {
+        match self {
+            Self::Point { name, .. } | Self::Rect { name, .. } => name,
+        }
+    }
+}
+
+fn write_tileset(tileset: &TiledTileset) -> Value {
+    match tileset {
+        TiledTileset::External { first_gid, source } => json!({ "firstgid": first_gid, "source": source }),
+        TiledTileset::Embedded {
+            first_gid,
+            name,
+            tile_width,
+            tile_height,
+            tile_count,
+            columns,
+            margin,
+            spacing,
+            image,
+            image_width,
+            image_height,
+        } => {
+            let mut out = json!({
+                "firstgid": first_gid, "name": name, "tilewidth": tile_width, "tileheight": tile_height,
+                "tilecount": tile_count, "columns": columns, "margin": margin, "spacing": spacing,
+            });
+            if let Some(image) = image {
+                out["image"] = json!(image);
+                out["imagewidth"] = json!(image_width);
+                out["imageheight"] = json!(image_height);
+            }
+            out
+        }
+    }
+}
+
+/// Cut a layer into chunks aligned to [`CHUNK`], from the top left.
+fn split_chunks(map: &TaxicabMap<u32>) -> Chunks {
+    let (x, y) = map.get_range();
+    let (left, top) = (x.start.div_euclid(CHUNK) * CHUNK, (1 - y.end).div_euclid(CHUNK) * CHUNK);
+    let mut chunks = vec![];
+    for cy in (top..1 - y.start).step_by(CHUNK as usize) {
+        for cx in (left..x.end).step_by(CHUNK as usize) {
+            chunks.push((cx, cy, tiles(map, cx, cy, CHUNK as usize, CHUNK as usize)));
+        }
+    }
+    Chunks { left, top, width: x.end - left, height: 1 - y.start - top, chunks }
+}
+
+/// Get the tiles of a block row by row from the top, `(left, top)` in Tiled coordinates.
+fn tiles(map: &TaxicabMap<u32>, left: isize, top: isize, width: usize, height: usize) -> Vec<u32> {
+    let (x_range, y_range) = map.get_range();
+    let mut out = Vec::with_capacity(width.checked_mul(height).unwrap_or_default());
+    for ty in top..top + height as isize {
+        for tx in left..left + width as isize {
+            // cyclic axes are not repeated outside of the map
+            match x_range.contains(&tx) && y_range.contains(&-ty) {
+                true => out.push(map.get_point(tx, -ty).copied().unwrap_or(0)),
+                false => out.push(0),
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,207 @@
+use super::*;
+use itertools::Itertools;
+use roxmltree::{Document, Node};
+use std::str::FromStr;
+
+impl TiledMap {
+    /// Read an orthogonal map, finite or infinite, from the Tiled XML format.
+    ///
+    /// Groups are flattened, returns `None` for other orientations or compressed layer data.
+    pub fn from_tmx(text: &str) -> Option<Self> {
+        let document = Document::parse(text).ok()?;
+        let root = document.root_element();
+        if !root.has_tag_name("map") || root.attribute("orientation").is_some_and(|o| o != "orthogonal") {
+            return None;
+        }
+        let mut out = Self::new(number(root, "tilewidth")?, number(root, "tileheight")?);
+        for tileset in root.children().filter(|n| n.has_tag_name("tileset")) {
+            out.add_tileset(read_tmx_tileset(tileset)?);
+        }
+        out.read_tmx_layers(root)?;
+        Some(out)
+    }
+    fn read_tmx_layers(&mut self, parent: Node) -> Option<()> {
+        for layer in parent.children().filter(Node::is_element) {
+            let name = layer.attribute("name").unwrap_or_default();
+            match layer.tag_name().name() {
+                "layer" => {
+                    let data = layer.children().find(|n| n.has_tag_name("data"))?;
+                    let encoding = data.attribute("encoding");
+                    let chunks = data.children().filter(|n| n.has_tag_name("chunk")).collect::<Vec<_>>();
+                    let map = match chunks.is_empty() {
+                        true => read_tmx_chunk(layer, data, encoding)?,
+                        false => {
+                            let chunks =
+                                chunks.into_iter().map(|c| read_tmx_chunk(c, c, encoding)).collect::<Option<Vec<_>>>()?;
+                            merge_chunks(chunks)?
+                        }
+                    };
+                    self.add_layer(name, map);
+                }
+                "objectgroup" => {
+                    let objects = layer.children().filter(|n| n.has_tag_name("object")).filter_map(|o| self.read_tmx_object(o));
+                    self.add_objects(name, objects.collect());
+                }
+                "group" => self.read_tmx_layers(layer)?,
+                _ => {}
+            }
+        }
+        Some(())
+    }
+    fn read_tmx_object(&self, object: Node) -> Option<TiledObject> {
+        let name = object.attribute("name").unwrap_or_default().to_string();
+        let (x, y) = (number(object, "x")?, number(object, "y")?);
+        let (width, height) = (number(object, "width").unwrap_or(0.0), number(object, "height").unwrap_or(0.0));
+        Some(self.tile_object(name, [x, y, width, height], object.children().any(|n| n.has_tag_name("point"))))
+    }
+}
+
+fn number<T: FromStr>(node: Node, key: &str) -> Option<T> {
+    node.attribute(key)?.parse().ok()
+}
+
+fn read_tmx_tileset(tileset: Node) -> Option<TiledTileset> {
+    let first_gid = number(tileset, "firstgid")?;
+    if let Some(source) = tileset.attribute("source") {
+        return Some(TiledTileset::External { first_gid, source: source.to_string() });
+    }
+    let image = tileset.children().find(|n| n.has_tag_name("image"));
+    Some(TiledTileset::Embedded {
+        first_gid,
+        name: tileset.attribute("name").unwrap_or_default().to_string(),
+        tile_width: number(tileset, "tilewidth").unwrap_or(0),
+        tile_height: number(tileset, "tileheight").unwrap_or(0),
+        tile_count: number(tileset, "tilecount").unwrap_or(0),
+        columns: number(tileset, "columns").unwrap_or(0),
+        margin: number(tileset, "margin").unwrap_or(0),
+        spacing: number(tileset, "spacing").unwrap_or(0),
+        image: image.and_then(|i| i.attribute("source")).map(str::to_string),
+        image_width: image.and_then(|i| number(i, "width")).unwrap_or(0),
+        image_height: image.and_then(|i| number(i, "height")).unwrap_or(0),
+    })
+}
+
+/// Read a finite layer or a chunk, the offset and size are on `block`, the tiles in `data`.
+fn read_tmx_chunk(block: Node, data: Node, encoding: Option<&str>) -> Option<TaxicabMap<u32>> {
+    let (width, height) = (number(block, "width")?, number(block, "height")?);
+    let (x, y) = (number(block, "x").unwrap_or(0), number(block, "y").unwrap_or(0));
+    let tiles = match encoding {
+        Some("csv") => {
+            let text = data.children().filter(Node::is_text).filter_map(|n| n.text()).collect::<String>();
+            text.split(',').map(str::trim).filter(|s| !s.is_empty()).map(|s| s.parse().ok()).collect::<Option<Vec<_>>>()?
+        }
+        None => data.children().filter(|n| n.has_tag_name("tile")).map(|n| number(n, "gid").unwrap_or(0)).collect(),
+        Some(_) => return None,
+    };
+    chunk_map(x, y, width, height, tiles)
+}
+
+impl TiledMap {
+    /// Write the map in the Tiled XML format, finite or infinite as in [`TiledMap::to_json`].
+    pub fn to_tmx(&self) -> String {
+        let finite = self.finite_size();
+        let mut body = String::new();
+        for tileset in &self.tilesets {
+            body.push_str(&write_tmx_tileset(tileset));
+        }
+        let mut id = 0;
+        let (mut width, mut height) = finite.unwrap_or_default();
+        for (name, map) in &self.layers {
+            id += 1;
+            let (w, h, data) = match finite {
+                Some(size) => (size.0 as isize, size.1 as isize, csv(&tiles(map, 0, 0, size.0, size.1), size.0)),
+                None => {
+                    let chunks = split_chunks(map);
+                    width = width.max(chunks.width as usize);
+                    height = height.max(chunks.height as usize);
+                    let data = chunks.chunks.iter().map(|(cx, cy, data)| {
+                        format!(
+                            "\n   <chunk x=\"{cx}\" y=\"{cy}\" width=\"{CHUNK}\" height=\"{CHUNK}\">\n{}\n   </chunk>",
+                            csv(data, CHUNK as usize)
+                        )
+                    });
+                    (chunks.width, chunks.height, data.collect::<String>())
+                }
+            };
+            body.push_str(&format!(
+                " <layer id=\"{id}\" name=\"{}\" width=\"{w}\" height=\"{h}\">\n  <data encoding=\"csv\">\n{data}\n  </data>\n </layer>\n",
+                escape(name)
+            ));
+        }
+        let mut object = 0;
+        for (name, objects) in &self.objects {
+            id += 1;
+            body.push_str(&format!(" <objectgroup id=\"{id}\" name=\"{}\">\n", escape(name)));
+            for o in objects {
+                object += 1;
+                let [x, y, w, h] = self.pixel_object(o);
+                let name = escape(o.get_name());
+                body.push_str(&match o {
+                    TiledObject::Point { .. } => {
+                        format!("  <object id=\"{object}\" name=\"{name}\" x=\"{x}\" y=\"{y}\">\n   <point/>\n  </object>\n")
+                    }
+                    TiledObject::Rect { .. } => {
+                        format!(
+                            "  <object id=\"{object}\" name=\"{name}\" x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\"/>\n"
+                        )
+                    }
+                });
+            }
+            body.push_str(" </objectgroup>\n");
+        }
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<map version=\"1.10\" orientation=\"orthogonal\" \
+             renderorder=\"right-down\" width=\"{width}\" height=\"{height}\" tilewidth=\"{}\" tileheight=\"{}\" \
+             infinite=\"{}\" nextlayerid=\"{}\" nextobjectid=\"{}\">\n{body}</map>\n",
+            self.tile_width,
+            self.tile_height,
+            finite.is_none() as u8,
+            id + 1,
+            object + 1
+        )
+    }
+}
+
+fn write_tmx_tileset(tileset: &TiledTileset) -> String {
+    match tileset {
+        TiledTileset::External { first_gid, source } => {
+            format!(" <tileset firstgid=\"{first_gid}\" source=\"{}\"/>\n", escape(source))
+        }
+        TiledTileset::Embedded {
+            first_gid,
+            name,
+            tile_width,
+            tile_height,
+            tile_count,
+            columns,
+            margin,
+            spacing,
+            image,
+            image_width,
+            image_height,
+        } => {
+            let mut out = format!(
+                " <tileset firstgid=\"{first_gid}\" name=\"{}\" tilewidth=\"{tile_width}\" tileheight=\"{tile_height}\" \
+                 spacing=\"{spacing}\" margin=\"{margin}\" tilecount=\"{tile_count}\" columns=\"{columns}\">\n",
+                escape(name)
+            );
+            if let Some(image) = image {
+                out.push_str(&format!(
+                    "  <image source=\"{}\" width=\"{image_width}\" height=\"{image_height}\"/>\n",
+                    escape(image)
+                ));
+            }
+            out.push_str(" </tileset>\n");
+            out
+        }
+    }
+}
+
+/// Write the tiles as comma separated rows.
+fn csv(tiles: &[u32], width: usize) -> String {
+    tiles.chunks(width.max(1)).map(|row| row.iter().join(",")).join(",\n")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,52 @@
+use super::*;
+use std::any::Any;
+
+/// A resize operation applied to all layers at once.
+pub(crate) enum Reshape {
+    Extend(Direction, usize),
+    Shrink(Direction, usize),
+    Crop(Range<isize>, Range<isize>),
+    Resize(usize, usize, Anchor, Anchor),
+}
+
+impl Reshape {
+    pub fn apply<T: Clone>(&self, map: &mut TaxicabMap<T>, fill: &T) {
+        match self {
+            Reshape::Extend(direction, size) => map.extend(*direction, *size, fill),
+            Reshape::Shrink(direction, size) => map.shrink(*direction, *size),
+            Reshape::Crop(x, y) => map.crop(x.clone(), y.clone()),
+            Reshape::Resize(w, h, anchor_x, anchor_y) => map.resize(*w, *h, *anchor_x, *anchor_y, fill),
+        }
+    }
+}
+
+pub(crate) struct Layer<T> {
+    pub map: TaxicabMap<T>,
+    pub fill: T,
+}
+
+pub(crate) trait AnyLayer {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn box_clone(&self) -> Box<dyn AnyLayer>;
+    fn reshape(&mut self, op: &Reshape);
+    fn sync(&mut self, geometry: &TaxicabMap<()>);
+}
+
+impl<T: Clone + 'static> AnyLayer for Layer<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn box_clone(&self) -> Box<dyn AnyLayer> {
+        Box::new(Layer { map: self.map.clone(), fill: self.fill.clone() })
+    }
+    fn reshape(&mut self, op: &Reshape) {
+        op.apply(&mut self.map, &self.fill)
+    }
+    fn sync(&mut self, geometry: &TaxicabMap<()>) {
+        self.map.copy_geometry(geometry)
+    }
+}
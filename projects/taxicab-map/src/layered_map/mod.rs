@@ -0,0 +1,180 @@
+use crate::{Anchor, Direction, TaxicabMap, Topology};
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Formatter},
+    ops::Range,
+};
+
+mod layer;
+
+use self::layer::{AnyLayer, Layer, Reshape};
+
+/// Multiple named layers of different types sharing one geometry, such as terrain, ownership and fog.
+///
+/// The size, origin, cycles, twists, mask and portals are always the same for all layers.
+pub struct LayeredMap {
+    geometry: TaxicabMap<()>,
+    layers: BTreeMap<String, Box<dyn AnyLayer>>,
+}
+
+impl Clone for LayeredMap {
+    fn clone(&self) -> Self {
+        let layers = self.layers.iter().map(|(k, v)| (k.clone(), v.box_clone())).collect();
+        Self { geometry: self.geometry.clone(), layers }
+    }
+}
+
+impl Debug for LayeredMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LayeredMap").field("geometry", &self.geometry).field("layers", &self.layers.keys()).finish()
+    }
+}
+
+impl LayeredMap {
+    /// Create a rectangle layered map without any layers
+    pub fn rectangle(width: usize, height: usize) -> Self {
+        Self::from_geometry(&TaxicabMap::rectangle(width, height, &()))
+    }
+    /// Create a layered map with the size, origin, cycles, twists, mask and portals of the map
+    pub fn from_geometry<T>(map: &TaxicabMap<T>) -> Self {
+        let mut geometry = TaxicabMap::rectangle(map.get_size().0, map.get_size().1, &());
+        geometry.copy_geometry(map);
+        Self { geometry, layers: BTreeMap::new() }
+    }
+    /// Get the shared geometry, which answers all queries about points, neighbors and paths.
+    pub fn geometry(&self) -> &TaxicabMap<()> {
+        &self.geometry
+    }
+    /// Add a layer filled with the value, the fill value is also used when the map grows.
+    ///
+    /// Returns false if the name is already used.
+    pub fn add_layer<T: Clone + 'static>(&mut self, name: &str, fill: T) -> bool {
+        if self.layers.contains_key(name) {
+            return false;
+        }
+        let (w, h) = self.geometry.get_size();
+        let mut map = TaxicabMap::rectangle(w, h, &fill);
+        map.copy_geometry(&self.geometry);
+        self.layers.insert(name.to_string(), Box::new(Layer { map, fill }));
+        true
+    }
+    /// Add a layer filled with the value, the fill value is also used when the map grows.
+    pub fn with_layer<T: Clone + 'static>(mut self, name: &str, fill: T) -> Self {
+        self.add_layer(name, fill);
+        self
+    }
+    /// Remove a layer
+    pub fn remove_layer(&mut self, name: &str) -> bool {
+        self.layers.remove(name).is_some()
+    }
+    /// Get the names of all layers
+    pub fn layer_names(&self) -> impl Iterator<Item = &str> {
+        self.layers.keys().map(|k| k.as_str())
+    }
+    /// Get a layer, returns `None` if the name is not found or the type does not match.
+    pub fn get_layer<T: 'static>(&self, name: &str) -> Option<&TaxicabMap<T>> {
+        let layer = self.layers.get(name)?.as_any().downcast_ref::<Layer<T>>()?;
+        Some(&layer.map)
+    }
+    fn mut_layer<T: 'static>(&mut self, name: &str) -> Option<&mut TaxicabMap<T>> {
+        let layer = self.layers.get_mut(name)?.as_any_mut().downcast_mut::<Layer<T>>()?;
+        Some(&mut layer.map)
+    }
+    /// Get the value of a point in a layer
+    pub fn get_point<T: 'static>(&self, name: &str, x: isize, y: isize) -> Option<&T> {
+        self.get_layer(name)?.get_point(x, y)
+    }
+    /// Get the mutable value of a point in a layer
+    pub fn mut_point<T: 'static>(&mut self, name: &str, x: isize, y: isize) -> Option<&mut T> {
+        self.mut_layer(name)?.mut_point(x, y)
+    }
+    /// Set the value of a point in a layer
+    pub fn set_point<T: 'static>(&mut self, name: &str, x: isize, y: isize, value: T) -> bool {
+        match self.mut_point(name, x, y) {
+            Some(v) => {
+                *v = value;
+                true
+            }
+            None => false,
+        }
+    }
+    /// Get a mutable iterator over all points in a layer
+    pub fn points_mut<T: 'static>(&mut self, name: &str) -> Option<impl Iterator<Item = (isize, isize, &mut T)>> {
+        Some(self.mut_layer(name)?.points_mut())
+    }
+    /// Traverse all points with the values of two layers
+    pub fn zip2<A: 'static, B: 'static>(&self, a: &str, b: &str) -> Option<impl Iterator<Item = (isize, isize, &A, &B)>> {
+        let a = self.get_layer::<A>(a)?.points_all();
+        let b = self.get_layer::<B>(b)?.points_all();
+        Some(a.zip(b).map(|((x, y, a), (_, _, b))| (x, y, a, b)))
+    }
+    /// Traverse all points with the values of three layers
+    pub fn zip3<A: 'static, B: 'static, C: 'static>(
+        &self,
+        a: &str,
+        b: &str,
+        c: &str,
+    ) -> Option<impl Iterator<Item = (isize, isize, &A, &B, &C)>> {
+        let a = self.get_layer::<A>(a)?.points_all();
+        let b = self.get_layer::<B>(b)?.points_all();
+        let c = self.get_layer::<C>(c)?.points_all();
+        Some(a.zip(b).zip(c).map(|(((x, y, a), (_, _, b)), (_, _, c))| (x, y, a, b, c)))
+    }
+}
+
+impl LayeredMap {
+    /// Extend all layers in a direction, fill with the fill value of each layer
+    pub fn extend(&mut self, direction: Direction, size: usize) {
+        self.reshape(Reshape::Extend(direction, size))
+    }
+    /// Remove rows or columns from one side of all layers
+    pub fn shrink(&mut self, direction: Direction, size: usize) {
+        self.reshape(Reshape::Shrink(direction, size))
+    }
+    /// Crop all layers to a rectangle in absolute coordinates
+    pub fn crop(&mut self, x: Range<isize>, y: Range<isize>) {
+        self.reshape(Reshape::Crop(x, y))
+    }
+    /// Resize all layers, fill with the fill value of each layer
+    pub fn resize(&mut self, width: usize, height: usize, anchor_x: Anchor, anchor_y: Anchor) {
+        self.reshape(Reshape::Resize(width, height, anchor_x, anchor_y))
+    }
+    /// Set the origin of all layers
+    pub fn set_origin(&mut self, x: isize, y: isize) {
+        self.geometry.set_origin(x, y);
+        self.sync();
+    }
+    /// Shift the origin of all layers
+    pub fn shift_origin(&mut self, x: isize, y: isize) {
+        self.geometry.shift_origin(x, y);
+        self.sync();
+    }
+    /// Set the cycle config of all layers
+    pub fn set_cycle(&mut self, cycle_x: bool, cycle_y: bool) {
+        self.geometry.set_cycle(cycle_x, cycle_y);
+        self.sync();
+    }
+    /// Set the topology of all layers
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.geometry.set_topology(topology);
+        self.sync();
+    }
+    /// Mark a point as existing or not in all layers
+    pub fn set_point_exists(&mut self, x: isize, y: isize, exists: bool) -> bool {
+        let success = self.geometry.set_point_exists(x, y, exists);
+        self.sync();
+        success
+    }
+    fn reshape(&mut self, op: Reshape) {
+        op.apply(&mut self.geometry, &());
+        for layer in self.layers.values_mut() {
+            layer.reshape(&op);
+        }
+        self.sync();
+    }
+    fn sync(&mut self) {
+        for layer in self.layers.values_mut() {
+            layer.sync(&self.geometry);
+        }
+    }
+}
@@ -2,17 +2,49 @@
 #![deny(missing_copy_implementations)]
 #![doc = include_str!("../readme.md")]
 
+mod analysis;
 mod dense_map;
 mod direction;
+mod edit;
+mod generator;
+mod io;
 mod joint;
+mod layered_map;
 mod path_finder;
+mod stacked_map;
 
+#[cfg(feature = "tiled")]
+pub use crate::io::tiled::{TiledMap, TiledObject, TiledTileset};
 pub use crate::{
+    analysis::{
+        influence::{Combine, Decay},
+        kernel::{Boundary, Kernel},
+    },
     dense_map::{
+        Anchor, GrowthPolicy, Portal, TaxicabMap, Topology,
+        action_field::ActionFieldSolver,
+        automaton::Automaton,
         iters::{DiamondPoints, GetTaxicabPoints, GetTaxicabPointsAround, MutGetTaxicabPoints},
-        TaxicabMap,
     },
     direction::Direction,
+    edit::{
+        diff::{MapDiff, MapEdit},
+        history::MapHistory,
+        tracker::TrackedMap,
+    },
+    generator::{
+        cave::{BspDungeon, CaveAutomaton, DrunkardWalk, Neighborhood},
+        maze::MazeAlgorithm,
+        noise::{NoiseField, NoiseKind},
+        wfc::TileSet,
+    },
+    io::{csv::CsvFormat, image::ImageRender, text::TextRender},
     joint::Joint,
+    layered_map::LayeredMap,
     path_finder::PathFinder,
+    stacked_map::{
+        StackedMap,
+        joint::{Direction3D, Joint3D, Point3D},
+        path_finder::StackedPathFinder,
+    },
 };
@@ -1,22 +1,21 @@
-use crate::{Joint, TaxicabMap};
+use crate::{Direction, Joint, TaxicabMap};
 use ordered_float::OrderedFloat;
 use pathfinding::prelude::astar;
-use std::collections::VecDeque;
 
 /// A* path finder on a taxicab map.
 pub struct PathFinder<'a, T> {
     map: &'a TaxicabMap<T>,
     start: (isize, isize),
     end: (isize, isize),
-    passable: Box<dyn Fn(isize, isize, &T) -> bool>,
-    action_cost: Box<dyn Fn(isize, isize, &T) -> f64>,
+    passable: PointFunction<T, bool>,
+    action_cost: PointFunction<T, f64>,
 }
 
+pub(crate) type PointFunction<T, R> = Box<dyn Fn(isize, isize, &T) -> R>;
+
 impl<T> TaxicabMap<T> {
     /// Create a path finder.
-    pub fn path_finder(&self, start: (isize, isize), end: (isize, isize)) -> PathFinder<T> {
-        let mut open = VecDeque::new();
-        open.push_back((0.0, start));
+    pub fn path_finder(&self, start: (isize, isize), end: (isize, isize)) -> PathFinder<'_, T> {
         PathFinder { map: self, start, end, passable: Box::new(|_, _, _| true), action_cost: Box::new(|_, _, _| 1.0) }
     }
 }
@@ -41,34 +40,25 @@ impl<'a, T> PathFinder<'a, T> {
 }
 
 impl<'a, T> PathFinder<'a, T> {
-    fn point_passable(&self, x: isize, y: isize) -> Option<OrderedFloat<f64>> {
-        let v = self.map.get_point(x, y)?;
-        if (self.passable)(x, y, v) {
-            let cost = (self.action_cost)(x, y, v);
-            Some(OrderedFloat(cost))
-        }
-        else {
-            None
-        }
+    fn neighbors(&self, point: (isize, isize)) -> Vec<((isize, isize), OrderedFloat<f64>)> {
+        walk_neighbors(self.map, &self.passable, &self.action_cost, point)
     }
-    fn neighbors(&self, (x, y): (isize, isize)) -> Vec<((isize, isize), OrderedFloat<f64>)> {
-        let mut out = Vec::with_capacity(4);
-        for (x, y) in self.map.points_nearby(x, y) {
-            match self.point_passable(x, y) {
-                Some(cost) => out.push(((x, y), cost)),
-                None => (),
-            }
+    // taxicab distance across cyclic seams as heuristic, portals and twists may shortcut the distance so it is disabled
+    fn heuristic(&self, point: (isize, isize)) -> OrderedFloat<f64> {
+        let ((cycle_x, cycle_y), (twist_x, twist_y)) = (self.map.get_cycle(), self.map.get_twist());
+        // a twist only takes effect across the seam of a cyclic axis
+        if self.map.portals().next().is_some() || cycle_x && twist_x || cycle_y && twist_y {
+            return OrderedFloat(0.0);
         }
-        out
-    }
-    // taxicab distance as heuristic
-    fn heuristic(&self, (x, y): (isize, isize)) -> OrderedFloat<f64> {
-        let dx = (x - self.end.0).abs();
-        let dy = (y - self.end.1).abs();
-        OrderedFloat((dx + dy) as f64)
+        OrderedFloat(self.map.taxicab_distance(point, self.end) as f64)
     }
     /// A* algorithm
-    pub fn solve_path(self) -> (Vec<(isize, isize)>, f64) {
+    pub fn solve_path(mut self) -> (Vec<(isize, isize)>, f64) {
+        self.start = self.map.wrap_point(self.start.0, self.start.1).unwrap_or(self.start);
+        self.end = self.map.wrap_point(self.end.0, self.end.1).unwrap_or(self.end);
+        self.solve_wrapped()
+    }
+    fn solve_wrapped(&self) -> (Vec<(isize, isize)>, f64) {
         astar(&self.start, |p| self.neighbors(*p), |p| self.heuristic(*p), |(x, y)| self.end == (*x, *y))
             .map(|(path, cost)| (path, cost.0))
             .unwrap_or((vec![], f64::INFINITY))
@@ -76,13 +66,43 @@ impl<'a, T> PathFinder<'a, T> {
     /// Solve by path and convert to joints
     pub fn solve_joint(self) -> (Vec<Joint>, f64) {
         let mut out = vec![];
+        let map = self.map;
         let (path, cost) = self.solve_path();
         if path.is_empty() {
             return (vec![], f64::INFINITY);
         }
         for (from, to) in path.iter().zip(path.iter().skip(1)) {
-            out.push(Joint::from_point(*from, *to))
+            let joint = Direction::all()
+                .into_iter()
+                .map(|direction| Joint::new(from.0, from.1, direction))
+                .find(|joint| map.joint_target(*joint).map(|j| j.source()) == Some(*to));
+            out.push(joint.unwrap_or_else(|| Joint::from_point(*from, *to)))
         }
         (out, cost)
     }
 }
+
+/// Get all passable points that can be reached in one step, with the cost of the step.
+pub(crate) fn walk_neighbors<T>(
+    map: &TaxicabMap<T>,
+    passable: &dyn Fn(isize, isize, &T) -> bool,
+    action_cost: &dyn Fn(isize, isize, &T) -> f64,
+    (x, y): (isize, isize),
+) -> Vec<((isize, isize), OrderedFloat<f64>)> {
+    let mut out = Vec::with_capacity(4);
+    for joint in map.joints_nearby(x, y) {
+        let (x, y) = match map.joint_target(joint) {
+            Some(target) => target.source(),
+            None => continue,
+        };
+        let v = match map.get_point(x, y) {
+            Some(v) if passable(x, y, v) => v,
+            _ => continue,
+        };
+        match map.get_portal(joint).and_then(|portal| portal.get_cost()) {
+            Some(cost) => out.push(((x, y), OrderedFloat(cost))),
+            None => out.push(((x, y), OrderedFloat(action_cost(x, y, v)))),
+        }
+    }
+    out
+}
@@ -0,0 +1,116 @@
+use super::*;
+
+/// A point on a level of a stacked map.
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Point3D {
+    x: isize,
+    y: isize,
+    z: isize,
+}
+
+/// Represents one of 6 directions over a stacked map.
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Direction3D {
+    /// - `S-false means left`
+    X(bool),
+    /// - `S-true means up`
+    Y(bool),
+    /// - `S-true means upstairs`
+    Z(bool),
+}
+
+/// A point on a level with the direction.
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Joint3D {
+    point: Point3D,
+    direction: Direction3D,
+}
+
+impl Point3D {
+    /// Create a new point on the level `z`.
+    pub fn new(x: isize, y: isize, z: isize) -> Self {
+        Self { x, y, z }
+    }
+    /// Get the point on the level.
+    pub fn planar(&self) -> (isize, isize) {
+        (self.x, self.y)
+    }
+    /// Get the level of the point.
+    pub fn level(&self) -> isize {
+        self.z
+    }
+}
+
+impl From<(isize, isize, isize)> for Point3D {
+    fn from((x, y, z): (isize, isize, isize)) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl Direction3D {
+    /// All 6 directions over a stacked map.
+    pub fn all() -> [Direction3D; 6] {
+        [
+            Direction3D::X(true),
+            Direction3D::X(false),
+            Direction3D::Y(true),
+            Direction3D::Y(false),
+            Direction3D::Z(true),
+            Direction3D::Z(false),
+        ]
+    }
+    /// Get the planar direction, returns `None` for vertical directions.
+    pub fn planar(&self) -> Option<Direction> {
+        match *self {
+            Direction3D::X(s) => Some(Direction::X(s)),
+            Direction3D::Y(s) => Some(Direction::Y(s)),
+            Direction3D::Z(_) => None,
+        }
+    }
+}
+
+impl From<Direction> for Direction3D {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::X(s) => Direction3D::X(s),
+            Direction::Y(s) => Direction3D::Y(s),
+        }
+    }
+}
+
+impl Not for Direction3D {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Direction3D::X(s) => Direction3D::X(!s),
+            Direction3D::Y(s) => Direction3D::Y(!s),
+            Direction3D::Z(s) => Direction3D::Z(!s),
+        }
+    }
+}
+
+impl Joint3D {
+    /// Create a new joint from a point and a direction.
+    pub fn new(point: Point3D, direction: Direction3D) -> Self {
+        Self { point, direction }
+    }
+    /// Create a new joint from a planar joint on the level `z`.
+    pub fn from_joint(joint: Joint, z: isize) -> Self {
+        let (x, y) = joint.source();
+        Self::new(Point3D::new(x, y, z), joint.get_direction().into())
+    }
+    /// Get the direction of the joint.
+    pub fn get_direction(&self) -> Direction3D {
+        self.direction
+    }
+    /// Get the source point of the joint.
+    pub fn source(&self) -> Point3D {
+        self.point
+    }
+    /// Get the planar joint, returns `None` for vertical joints.
+    pub fn planar(&self) -> Option<Joint> {
+        let (x, y) = self.point.planar();
+        Some(Joint::new(x, y, self.direction.planar()?))
+    }
+}
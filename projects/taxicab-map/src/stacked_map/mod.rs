@@ -0,0 +1,129 @@
+use crate::{Direction, Joint, TaxicabMap, dense_map::pairs};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, ops::Not};
+
+pub mod joint;
+pub mod path_finder;
+
+use self::joint::{Direction3D, Joint3D, Point3D};
+
+/// Multiple taxicab maps stacked as levels, connected by vertical links such as stairs.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct StackedMap<T> {
+    levels: BTreeMap<isize, TaxicabMap<T>>,
+    #[serde(with = "pairs")]
+    links: BTreeMap<Joint3D, Point3D>,
+}
+
+impl<T> Default for StackedMap<T> {
+    fn default() -> Self {
+        Self { levels: BTreeMap::new(), links: BTreeMap::new() }
+    }
+}
+
+impl<T> StackedMap<T> {
+    /// Insert a level, each level keeps its own size and origin.
+    pub fn insert_level(&mut self, z: isize, level: TaxicabMap<T>) -> Option<TaxicabMap<T>> {
+        self.levels.insert(z, level)
+    }
+    /// Insert a level, each level keeps its own size and origin.
+    pub fn with_level(mut self, z: isize, level: TaxicabMap<T>) -> Self {
+        self.insert_level(z, level);
+        self
+    }
+    /// Remove a level and all links from or to it.
+    pub fn remove_level(&mut self, z: isize) -> Option<TaxicabMap<T>> {
+        self.links.retain(|k, v| k.source().level() != z && v.level() != z);
+        self.levels.remove(&z)
+    }
+    /// Get the level
+    pub fn get_level(&self, z: isize) -> Option<&TaxicabMap<T>> {
+        self.levels.get(&z)
+    }
+    /// Get the mutable level
+    pub fn mut_level(&mut self, z: isize) -> Option<&mut TaxicabMap<T>> {
+        self.levels.get_mut(&z)
+    }
+    /// Get all levels from the bottom to the top
+    pub fn levels(&self) -> impl Iterator<Item = (isize, &TaxicabMap<T>)> {
+        self.levels.iter().map(|(z, level)| (*z, level))
+    }
+    /// Check if the point exists on its level
+    pub fn has_point(&self, point: Point3D) -> bool {
+        let (x, y) = point.planar();
+        self.get_level(point.level()).is_some_and(|level| level.has_point(x, y))
+    }
+    /// Get the value of a point
+    pub fn get_point(&self, point: Point3D) -> Option<&T> {
+        let (x, y) = point.planar();
+        self.get_level(point.level())?.get_point(x, y)
+    }
+    /// Get the mutable value of a point
+    pub fn mut_point(&mut self, point: Point3D) -> Option<&mut T> {
+        let (x, y) = point.planar();
+        self.mut_level(point.level())?.mut_point(x, y)
+    }
+    /// Count all defined points in all levels.
+    pub fn count_points(&self) -> usize {
+        self.levels.values().map(|level| level.count_points()).sum()
+    }
+    /// Link a point to a point on another level, returns false if a point does not exist or both are on the same level.
+    pub fn add_link(&mut self, from: Point3D, to: Point3D) -> bool {
+        let (from, to) = match (self.wrap_point(from), self.wrap_point(to)) {
+            (Some(from), Some(to)) if from.level() != to.level() => (from, to),
+            _ => return false,
+        };
+        self.links.insert(Joint3D::new(from, Direction3D::Z(to.level() > from.level())), to);
+        true
+    }
+    /// Link two points on different levels in both ways, like stairs.
+    pub fn add_link_both(&mut self, a: Point3D, b: Point3D) -> bool {
+        self.add_link(a, b) && self.add_link(b, a)
+    }
+    /// Remove the vertical link at the joint.
+    pub fn remove_link(&mut self, joint: Joint3D) -> Option<Point3D> {
+        self.links.remove(&joint)
+    }
+    /// Get all vertical links in the map.
+    pub fn links(&self) -> impl Iterator<Item = (Joint3D, Point3D)> + '_ {
+        self.links.iter().map(|(k, v)| (*k, *v))
+    }
+    /// Walk through a joint, planar joints respect the topology and portals of the level.
+    pub fn joint_target(&self, joint: Joint3D) -> Option<Point3D> {
+        let z = joint.source().level();
+        match joint.planar() {
+            Some(planar) => {
+                let (x, y) = self.get_level(z)?.joint_target(planar)?.source();
+                Some(Point3D::new(x, y, z))
+            }
+            None => self.links.get(&self.wrap_joint(joint)?).copied(),
+        }
+    }
+    /// Find at most 6 joints that are exists and adjacent to a point, including vertical links.
+    pub fn joints_nearby(&self, point: Point3D) -> impl Iterator<Item = Joint3D> + '_ {
+        Direction3D::all().into_iter().map(move |d| Joint3D::new(point, d)).filter(|joint| self.joint_target(*joint).is_some())
+    }
+    /// Find at most 6 points that are exists and adjacent to a point, including vertical links.
+    pub fn points_nearby(&self, point: Point3D) -> impl Iterator<Item = Point3D> + '_ {
+        Direction3D::all().into_iter().filter_map(move |d| self.joint_target(Joint3D::new(point, d)))
+    }
+    fn wrap_point(&self, point: Point3D) -> Option<Point3D> {
+        let (x, y) = point.planar();
+        let (x, y) = self.get_level(point.level())?.wrap_point(x, y)?;
+        Some(Point3D::new(x, y, point.level()))
+    }
+    fn wrap_joint(&self, joint: Joint3D) -> Option<Joint3D> {
+        Some(Joint3D::new(self.wrap_point(joint.source())?, joint.get_direction()))
+    }
+}
+
+impl<T: Clone> StackedMap<T> {
+    /// Set the value of a point, the level grows by its growth policy.
+    pub fn set_point(&mut self, point: Point3D, value: T) -> bool {
+        let (x, y) = point.planar();
+        match self.mut_level(point.level()) {
+            Some(level) => level.set_point(x, y, value),
+            None => false,
+        }
+    }
+}
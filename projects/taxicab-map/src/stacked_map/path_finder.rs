@@ -0,0 +1,77 @@
+use super::*;
+use ordered_float::OrderedFloat;
+use pathfinding::prelude::dijkstra;
+
+type PointFunction<T, R> = Box<dyn Fn(Point3D, &T) -> R>;
+
+/// Dijkstra path finder across the levels of a stacked map.
+pub struct StackedPathFinder<'a, T> {
+    map: &'a StackedMap<T>,
+    start: Point3D,
+    end: Point3D,
+    passable: PointFunction<T, bool>,
+    action_cost: PointFunction<T, f64>,
+}
+
+impl<T> StackedMap<T> {
+    /// Create a path finder.
+    pub fn path_finder(&self, start: Point3D, end: Point3D) -> StackedPathFinder<'_, T> {
+        StackedPathFinder { map: self, start, end, passable: Box::new(|_, _| true), action_cost: Box::new(|_, _| 1.0) }
+    }
+}
+
+impl<'a, T> StackedPathFinder<'a, T> {
+    /// Set the passable function.
+    pub fn with_passable<F>(mut self, passable: F) -> Self
+    where
+        F: Fn(Point3D, &T) -> bool + 'static,
+    {
+        self.passable = Box::new(passable);
+        self
+    }
+    /// Set the action cost function.
+    pub fn with_action_cost<F>(mut self, cost: F) -> Self
+    where
+        F: Fn(Point3D, &T) -> f64 + 'static,
+    {
+        self.action_cost = Box::new(cost);
+        self
+    }
+}
+
+impl<'a, T> StackedPathFinder<'a, T> {
+    fn neighbors(&self, point: Point3D) -> Vec<(Point3D, OrderedFloat<f64>)> {
+        let mut out = Vec::with_capacity(6);
+        for target in self.map.points_nearby(point) {
+            match self.map.get_point(target) {
+                Some(v) if (self.passable)(target, v) => out.push((target, OrderedFloat((self.action_cost)(target, v)))),
+                _ => continue,
+            }
+        }
+        out
+    }
+    /// Dijkstra algorithm, vertical links may connect any points so no heuristic is used
+    pub fn solve_path(self) -> (Vec<Point3D>, f64) {
+        let start = self.map.wrap_point(self.start).unwrap_or(self.start);
+        let end = self.map.wrap_point(self.end).unwrap_or(self.end);
+        dijkstra(&start, |p| self.neighbors(*p), |p| *p == end)
+            .map(|(path, cost)| (path, cost.0))
+            .unwrap_or((vec![], f64::INFINITY))
+    }
+    /// Solve by path and convert to joints
+    pub fn solve_joint(self) -> (Vec<Joint3D>, f64) {
+        let map = self.map;
+        let (path, cost) = self.solve_path();
+        if path.is_empty() {
+            return (vec![], f64::INFINITY);
+        }
+        let mut out = vec![];
+        for (from, to) in path.iter().zip(path.iter().skip(1)) {
+            match map.joints_nearby(*from).find(|joint| map.joint_target(*joint) == Some(*to)) {
+                Some(joint) => out.push(joint),
+                None => unreachable!("{:?} and {:?} are not adjacent", from, to),
+            }
+        }
+        (out, cost)
+    }
+}
@@ -1,5 +1,11 @@
 use itertools::Itertools;
-use taxicab_map::{DiamondPoints, TaxicabMap};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::collections::BTreeSet;
+use taxicab_map::{
+    Anchor, Automaton, Boundary, BspDungeon, CaveAutomaton, Combine, CsvFormat, Decay, DiamondPoints, Direction, Direction3D,
+    DrunkardWalk, GrowthPolicy, Joint, Joint3D, Kernel, LayeredMap, MapDiff, MapEdit, MapHistory, MazeAlgorithm, Neighborhood,
+    NoiseField, NoiseKind, Point3D, Portal, StackedMap, TaxicabMap, TileSet, Topology, TrackedMap,
+};
 
 #[test]
 fn ready() {
@@ -56,3 +62,897 @@ fn test_path() {
         println!("{j}: {cost}")
     }
 }
+
+#[test]
+fn test_path_seam() {
+    // the shortest path goes around the wall across the seam
+    let map = TaxicabMap::<usize>::rectangle(10, 3, &1).with_cycle(true, false);
+    let (path, cost) = map.path_finder((0, 0), (8, 0)).with_passable(|x, y, _| (x, y) != (9, 0)).solve_path();
+    assert_eq!(cost, 4.0);
+    assert!(path.contains(&(9, 1)));
+    // a twist without a cycle has no seam to cross
+    let flat = TaxicabMap::<usize>::rectangle(10, 3, &1).with_twist(true, false);
+    assert_eq!(flat.path_finder((0, 0), (8, 0)).with_passable(|x, y, _| (x, y) != (1, 0)).solve_path().1, 10.0);
+    let map = map.with_topology(Topology::Mobius);
+    let (_, cost) = map.path_finder((0, 0), (9, 2)).solve_path();
+    assert_eq!(cost, 1.0);
+}
+
+#[test]
+fn test_resize() {
+    let mut map = TaxicabMap::<usize>::square(3, &0);
+    map.set_point(1, 1, 5);
+    map.extend(Direction::X(true), 2, &0);
+    assert_eq!(map.get_origin(), (-2, 0));
+    assert_eq!(map.get_point(1, 1), Some(&5));
+    map.extend(Direction::Y(false), 2, &0);
+    assert_eq!(map.get_size(), (5, 5));
+    map.crop(-1..2, 0..9);
+    assert_eq!(map.get_range(), (-1..2, 0..5));
+    assert_eq!(map.get_point(1, 1), Some(&5));
+    map.shrink(Direction::Y(true), 1);
+    assert_eq!(map.get_range(), (-1..2, 1..5));
+    map.resize(7, 6, Anchor::Center, Anchor::End, &0);
+    assert_eq!(map.get_range(), (-3..4, -1..5));
+    assert_eq!(map.get_point(1, 1), Some(&5));
+    map.shrink_to_fit(&0);
+    assert_eq!(map.get_range(), (1..2, 1..2));
+    assert_eq!(map.count_points(), 1);
+    // true grows the low side and false the high side, like the baseline
+    let mut map = TaxicabMap::<usize>::rectangle(2, 2, &0);
+    map.set_point(0, 0, 5);
+    map.extend(Direction::X(true), 1, &1);
+    map.extend(Direction::Y(true), 1, &1);
+    assert_eq!(map.get_range(), (-1..2, -1..2));
+    map.extend(Direction::X(false), 1, &2);
+    map.extend(Direction::Y(false), 1, &2);
+    assert_eq!(map.get_range(), (-1..3, -1..3));
+    assert_eq!((map[(0, 0)], map[(-1, 0)], map[(2, 0)], map[(0, 2)]), (5, 1, 2, 2));
+    map.shrink(Direction::X(true), 1);
+    map.shrink(Direction::Y(false), 1);
+    assert_eq!(map.get_range(), (0..3, -1..2));
+}
+
+#[test]
+fn test_growth() {
+    let mut map = TaxicabMap::<usize>::square(2, &0);
+    assert!(!map.set_point(3, 0, 1));
+    map.set_growth(GrowthPolicy::Exact, 0);
+    assert_eq!(map, TaxicabMap::square(2, &0), "the growth policy is not compared");
+    assert!(map.set_point(3, -1, 1));
+    assert_eq!(map.get_range(), (0..4, -1..2));
+    map.set_growth(GrowthPolicy::Double, 0);
+    assert!(map.set_point(-1, 0, 2));
+    assert_eq!(map.get_range(), (-4..4, -1..2));
+    map.set_growth(GrowthPolicy::Chunk(8), 0);
+    *map.mut_point_or_insert(0, 2).unwrap() = 3;
+    assert_eq!(map.get_range(), (-4..4, -1..10));
+    assert_eq!((map[(3, -1)], map[(-1, 0)], map[(0, 2)]), (1, 2, 3));
+    let mut map = TaxicabMap::<usize>::square(2, &0).with_cycle(true, false).with_growth(GrowthPolicy::Exact, 0);
+    assert!(map.set_point(5, 3, 1));
+    assert_eq!(map.get_size(), (2, 4));
+    // values that are not Clone can still be written
+    struct Cell(usize);
+    let mut cells = map.map_values(|_, _, v| Cell(*v));
+    assert!(cells.set_point(0, 0, Cell(9)));
+    assert!(!cells.set_point(0, 9, Cell(9)));
+    assert_eq!(cells[(0, 0)].0, 9);
+}
+
+#[test]
+fn test_mask() {
+    let mut map = TaxicabMap::<usize>::diamond(2, &1);
+    assert_eq!(map.count_points(), 13);
+    assert_eq!(map.points_all().count(), 13);
+    assert!(map.has_point(0, 2) && !map.has_point(1, 2));
+    assert_eq!(map.get_point(2, 2), None);
+    assert_eq!(map.points_nearby(2, 0).collect_vec(), vec![(1, 0)]);
+    map.set_point_exists(0, 1, false);
+    let (path, _) = map.path_finder((1, 1), (-1, 1)).solve_path();
+    assert_eq!(path.len(), 5);
+    assert!(!path.contains(&(0, 1)));
+    // lines skip holes and yield absolute coordinates
+    let rows = map.rows(true).map(|row| row.into_iter().map(|(x, y, _)| (x, y)).collect_vec()).collect_vec();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(rows[0], vec![(0, -2)]);
+    assert_eq!(rows[3], vec![(-1, 1), (1, 1)]);
+    assert_eq!(rows.iter().map(|row| row.len()).sum::<usize>(), map.count_points());
+    assert_eq!(map.columns(false).next().unwrap(), vec![(2, 0, &1)]);
+    assert!(std::panic::catch_unwind(|| map[(0, 1)]).is_err(), "indexing a hole panics");
+    map.extend(Direction::X(false), 1, &1);
+    assert!(map.has_point(3, 2) && !map.has_point(2, 2));
+    let copy = TaxicabMap::from_mask(&map.get_mask(), &0usize);
+    assert_eq!(copy.count_points(), map.count_points());
+}
+
+#[test]
+fn test_topology() {
+    let mut map = TaxicabMap::<usize>::rectangle(4, 3, &0).with_topology(Topology::Mobius);
+    assert_eq!(map.get_topology(), Topology::Mobius);
+    map.set_point(0, 0, 1);
+    assert_eq!(map.wrap_point(4, 2), Some((0, 0)));
+    assert_eq!(map[(4, 2)], 1);
+    assert_eq!(map.get_point(4, 3), None);
+    let joint = map.joint_target(Joint::new(3, 0, Direction::X(true))).unwrap();
+    assert_eq!(joint, Joint::new(0, 2, Direction::X(true)));
+    let joint = map.wrap_joint(Joint::new(4, 2, Direction::Y(true))).unwrap();
+    assert_eq!(joint, Joint::new(0, 0, Direction::Y(false)));
+    let (path, _) = map.path_finder((3, 0), (0, 2)).solve_joint();
+    assert_eq!(path, vec![Joint::new(3, 0, Direction::X(true))]);
+    map.set_topology(Topology::Projective);
+    assert_eq!(map.wrap_point(-1, 0), Some((3, 2)));
+    assert_eq!(map.wrap_point(1, -1), Some((2, 2)));
+    assert_eq!(map.points_nearby(0, 0).filter_map(|(x, y)| map.wrap_point(x, y)).count(), 4);
+}
+
+#[test]
+fn test_portal() {
+    let mut map = TaxicabMap::<usize>::rectangle(10, 1, &1);
+    assert!(
+        map.add_portal(
+            Joint::new(0, 0, Direction::X(false)),
+            Portal::new(Joint::new(9, 0, Direction::X(false))).with_cost(0.5)
+        )
+    );
+    assert!(!map.add_portal(Joint::new(0, 0, Direction::Y(true)), Portal::new(Joint::new(20, 0, Direction::X(true)))));
+    assert_eq!(map.points_nearby(0, 0).collect_vec(), vec![(1, 0), (9, 0)]);
+    assert_eq!(map.joints_nearby(0, 0).count(), 2);
+    assert_eq!(map.joint_target(Joint::new(0, 0, Direction::X(false))), Some(Joint::new(9, 0, Direction::X(false))));
+    let (path, cost) = map.path_finder((0, 0), (8, 0)).solve_joint();
+    assert_eq!(path, vec![Joint::new(0, 0, Direction::X(false)), Joint::new(9, 0, Direction::X(false))]);
+    assert_eq!(cost, 1.5);
+    let field = map.action_field((0, 0), 1.0).solve();
+    assert_eq!(field, vec![(0.0, (0, 0)), (0.5, (9, 0)), (1.0, (1, 0))]);
+    assert!(map.remove_portal(Joint::new(0, 0, Direction::X(false))).is_some());
+    assert_eq!(map.path_finder((0, 0), (8, 0)).solve_path().1, 8.0);
+    // portals survive json, move with the origin and are dropped with their cells
+    map.add_portal(Joint::new(0, 0, Direction::X(false)), Portal::new(Joint::new(9, 0, Direction::X(false))));
+    map.add_portal(Joint::new(4, 0, Direction::Y(true)), Portal::new(Joint::new(5, 0, Direction::Y(true))));
+    let json = serde_json::to_string(&map).unwrap();
+    assert_eq!(serde_json::from_str::<TaxicabMap<usize>>(&json).unwrap(), map);
+    map.shift_origin(3, 0);
+    assert_eq!(map.joint_target(Joint::new(3, 0, Direction::X(false))), Some(Joint::new(12, 0, Direction::X(false))));
+    map.crop(3..10, 0..1);
+    assert_eq!(map.portals().count(), 1);
+    let mut bytes = vec![];
+    map.write_binary(&mut bytes, |v| v.to_le_bytes().to_vec()).unwrap();
+    let back = TaxicabMap::read_binary(bytes.as_slice(), |b| Some(usize::from_le_bytes(b.try_into().ok()?))).unwrap();
+    assert_eq!(back, map);
+    map.set_point_exists(8, 0, false);
+    assert_eq!(map.portals().count(), 0);
+}
+
+#[test]
+fn test_stacked() {
+    let mut map = StackedMap::default()
+        .with_level(0, TaxicabMap::<usize>::square(3, &1))
+        .with_level(1, TaxicabMap::<usize>::square(3, &1).with_origin(10, 10));
+    assert_eq!(map.count_points(), 18);
+    assert!(!map.add_link(Point3D::new(2, 2, 0), Point3D::new(0, 0, 1)));
+    assert!(map.add_link_both(Point3D::new(2, 2, 0), Point3D::new(10, 10, 1)));
+    assert_eq!(map.points_nearby(Point3D::new(2, 2, 0)).count(), 3);
+    assert_eq!(map.joint_target(Joint3D::new(Point3D::new(10, 10, 1), Direction3D::Z(false))), Some(Point3D::new(2, 2, 0)));
+    let (path, cost) = map.path_finder(Point3D::new(0, 0, 0), Point3D::new(12, 12, 1)).solve_joint();
+    assert_eq!(cost, 9.0);
+    assert_eq!(path[4], Joint3D::new(Point3D::new(2, 2, 0), Direction3D::Z(true)));
+    let json = serde_json::to_string(&map).unwrap();
+    assert_eq!(serde_json::from_str::<StackedMap<usize>>(&json).unwrap(), map);
+    map.remove_level(1);
+    assert_eq!(map.links().count(), 0);
+}
+
+#[test]
+fn test_layers() {
+    let mut map = LayeredMap::rectangle(3, 2).with_layer("terrain", 'g').with_layer("height", 0.0f64);
+    assert!(!map.add_layer("terrain", 0u8));
+    assert!(map.get_layer::<u8>("terrain").is_none());
+    map.set_point("terrain", 2, 1, 'w');
+    map.set_point("height", 2, 1, 1.5);
+    map.extend(Direction::X(true), 1);
+    map.set_point_exists(0, 0, false);
+    assert_eq!(map.geometry().get_range(), (-1..3, 0..2));
+    assert_eq!(map.get_layer::<char>("terrain").unwrap().get_range(), (-1..3, 0..2));
+    assert_eq!(map.get_layer::<f64>("height").unwrap().count_points(), 7);
+    let cells = map.zip2::<char, f64>("terrain", "height").unwrap().collect_vec();
+    assert_eq!(cells.len(), 7);
+    assert!(cells.contains(&(2, 1, &'w', &1.5)));
+    assert!(cells.contains(&(-1, 0, &'g', &0.0)));
+    for (_, _, v) in map.points_mut::<f64>("height").unwrap() {
+        *v += 1.0;
+    }
+    assert_eq!(map.get_point::<f64>("height", 2, 1), Some(&2.5));
+}
+
+fn assert_perfect_maze(map: &TaxicabMap<usize>, open: &BTreeSet<Joint>) {
+    assert_eq!(open.len(), 2 * (map.count_points() - 1));
+    let mut visited = BTreeSet::from([(0, 0)]);
+    let mut stack = vec![(0, 0)];
+    while let Some((x, y)) = stack.pop() {
+        for joint in open.iter().filter(|j| j.source() == (x, y)) {
+            let target = map.joint_target(*joint).unwrap().source();
+            if visited.insert(target) {
+                stack.push(target);
+            }
+        }
+    }
+    assert_eq!(visited.len(), map.count_points());
+}
+
+#[test]
+fn test_maze() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let plane = TaxicabMap::<usize>::rectangle(7, 5, &0);
+    let torus = plane.clone().with_cycle(true, true);
+    for algorithm in [
+        MazeAlgorithm::Backtracker,
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Wilson,
+        MazeAlgorithm::Eller,
+        MazeAlgorithm::BinaryTree,
+        MazeAlgorithm::Sidewinder,
+    ] {
+        assert_perfect_maze(&plane, &plane.maze_joints(algorithm, &mut rng));
+        assert_perfect_maze(&torus, &torus.maze_joints(algorithm, &mut rng));
+    }
+    let maze = plane.maze_map(MazeAlgorithm::Wilson, &mut rng, &'#', &' ');
+    assert_eq!(maze.get_size(), (15, 11));
+    assert_eq!(maze.points_all().filter(|(_, _, c)| **c == ' ').count(), 35 + 34);
+    let a = plane.maze_joints(MazeAlgorithm::Prim, &mut StdRng::seed_from_u64(1));
+    let b = plane.maze_joints(MazeAlgorithm::Prim, &mut StdRng::seed_from_u64(1));
+    assert_eq!(a, b);
+    // a one-way portal into a walled off cell leaves walks from it nowhere to go
+    let mut pit = TaxicabMap::<usize>::square(3, &0);
+    pit.set_point_exists(1, 2, false);
+    pit.set_point_exists(2, 1, false);
+    pit.add_portal(Joint::new(0, 0, Direction::X(false)), Portal::new(Joint::new(2, 2, Direction::X(false))));
+    for seed in 0..20 {
+        let open = pit.maze_joints(MazeAlgorithm::Wilson, &mut StdRng::seed_from_u64(seed));
+        assert!(open.len() == 2 * 5 || open.len() == 2 * 6, "{:?}", open);
+    }
+}
+
+#[test]
+fn test_cave() {
+    let generate = |seed: u64, kind: usize| {
+        let mut map = TaxicabMap::rectangle(40, 30, &'#');
+        let mut rng = StdRng::seed_from_u64(seed);
+        match kind {
+            0 => CaveAutomaton::default().generate(&mut map, &'#', &'.', &mut rng),
+            1 => CaveAutomaton::default()
+                .with_neighborhood(Neighborhood::VonNeumann(2))
+                .with_rule(&[8, 9, 10, 11, 12], &[6, 7, 8, 9, 10, 11, 12])
+                .generate(&mut map, &'#', &'.', &mut rng),
+            2 => BspDungeon::new(8, 3).generate(&mut map, &'#', &'.', &mut rng),
+            _ => DrunkardWalk::new(0.3).with_walkers(3).generate(&mut map, &'#', &'.', &mut rng),
+        }
+        map
+    };
+    for kind in 0..4 {
+        let mut map = generate(7, kind);
+        assert!(map.points_all().any(|(_, _, v)| *v == '.'));
+        assert_eq!(map.connect_floors(&'.'), 0, "all floors are connected");
+        assert_eq!(map, generate(7, kind), "same seed generates same map");
+    }
+    // out of range fill ratios are clamped instead of panicking
+    for fill in [-0.5, 1.5, f64::NAN] {
+        let mut map = TaxicabMap::rectangle(8, 8, &'#');
+        CaveAutomaton::default().with_fill(fill).generate(&mut map, &'#', &'.', &mut StdRng::seed_from_u64(1));
+    }
+    // scattered floors are joined through one search, with the fewest carved points along a line
+    let mut dots = TaxicabMap::rectangle(9, 1, &'#');
+    for x in [0, 4, 8] {
+        dots.set_point(x, 0, '.');
+    }
+    assert_eq!(dots.connect_floors(&'.'), 6);
+    assert!(dots.points_all().all(|(_, _, v)| *v == '.'));
+    let mut grid = TaxicabMap::rectangle(30, 30, &'#');
+    for (x, y) in (0..30).step_by(3).cartesian_product((0..30).step_by(3)) {
+        grid.set_point(x, y, '.');
+    }
+    assert_eq!(grid.connect_floors(&'.'), 99 * 2);
+    assert_eq!(grid.connect_floors(&'.'), 0);
+    assert_eq!(Neighborhood::VonNeumann(2).offsets().len(), 12);
+    assert_eq!(Neighborhood::Moore(1).offsets().len(), 8);
+}
+
+#[test]
+fn test_wfc() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut checker = TileSet::default().with_tile('a', 1.0).with_tile('b', 1.0);
+    for direction in Direction::all() {
+        checker.allow(&'a', direction, &'b');
+    }
+    let mut map = TaxicabMap::<Option<char>>::square(4, &None);
+    map.set_point(1, 0, Some('a'));
+    assert!(checker.collapse(&mut map, &mut rng));
+    for (x, y, v) in map.points_all() {
+        assert_eq!(*v, Some(if (x + y) % 2 == 1 { 'a' } else { 'b' }));
+    }
+    let mut odd = TaxicabMap::<Option<char>>::square(3, &None).with_cycle(true, true);
+    assert!(!checker.collapse(&mut odd, &mut rng));
+    assert!(odd.points_all().all(|(_, _, v)| v.is_none()));
+
+    let mut sample = TaxicabMap::rectangle(6, 5, &'~');
+    for x in 0..6 {
+        sample.set_point(x, 0, '#');
+        sample.set_point(x, 1, '#');
+        sample.set_point(x, 2, '.');
+    }
+    let tiles = TileSet::learn(&sample);
+    let mut map = TaxicabMap::<Option<char>>::rectangle(10, 8, &None);
+    assert!(tiles.collapse(&mut map, &mut rng));
+    for (x, y, v) in map.points_all() {
+        if let Some(up) = map.get_point(x, y + 1) {
+            let pair = (v.unwrap(), up.unwrap());
+            assert!(pair.0 == pair.1 || pair == ('#', '.') || pair == ('.', '~'));
+        }
+    }
+}
+
+#[test]
+fn test_noise() {
+    for kind in [NoiseKind::Value, NoiseKind::Perlin] {
+        let noise = NoiseField::new(kind, 9).with_frequency(0.15).with_octaves(4).with_persistence(0.5).with_lacunarity(2.0);
+        let mut whole = TaxicabMap::rectangle(16, 8, &0.0);
+        let mut left = TaxicabMap::rectangle(8, 8, &0.0);
+        let mut right = TaxicabMap::rectangle(8, 8, &0.0).with_origin(8, 0);
+        noise.fill(&mut whole);
+        noise.fill(&mut left);
+        noise.fill(&mut right);
+        for (x, y, v) in whole.points_all() {
+            assert!((-1.0..=1.0).contains(v));
+            let chunk = if x < 8 { &left } else { &right };
+            assert_eq!(chunk.get_point(x, y), Some(v), "chunks tile seamlessly");
+        }
+        let mut torus = TaxicabMap::square(12, &0.0).with_cycle(true, true);
+        let mut shifted = torus.clone().with_origin(12, -24);
+        noise.fill(&mut torus);
+        noise.fill(&mut shifted);
+        for (x, y, v) in torus.points_all() {
+            assert_eq!(shifted[(x + 12, y - 24)], *v, "cyclic axes tile perfectly");
+        }
+        assert_ne!(NoiseField::new(kind, 1).sample(3.5, 4.5), NoiseField::new(kind, 2).sample(3.5, 4.5));
+    }
+}
+
+fn life(_: isize, _: isize, alive: &bool, around: &[Option<&bool>]) -> bool {
+    let count = around.iter().filter(|v| matches!(v, Some(true))).count();
+    matches!((alive, count), (true, 2) | (_, 3))
+}
+
+#[test]
+fn test_automaton() {
+    let mut map = TaxicabMap::square(6, &false).with_cycle(true, true);
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        map.set_point(x, y, true);
+    }
+    let mut life_game = Automaton::new(map.clone(), Neighborhood::Moore(1));
+    for _ in 0..24 {
+        life_game.step(life);
+    }
+    assert_eq!(life_game.get_generation(), 24);
+    assert_eq!(life_game.get_map(), &map, "glider returns after 4 steps per cell on a torus");
+    #[cfg(feature = "rayon")]
+    {
+        let mut parallel = Automaton::new(map.clone(), Neighborhood::Moore(1));
+        let mut serial = parallel.clone();
+        parallel.step_parallel(life);
+        serial.step(life);
+        assert_eq!(parallel.get_map(), serial.get_map());
+    }
+    let mut fire = Automaton::new(TaxicabMap::rectangle(5, 1, &0u8), Neighborhood::VonNeumann(1));
+    fire.mut_map().set_point(0, 0, 1);
+    fire.step(|_, _, v, around| if around.iter().any(|n| n == &Some(&1)) { 1 } else { *v });
+    assert_eq!(fire.into_map().points_all().map(|(_, _, v)| *v).collect_vec(), vec![1, 1, 0, 0, 0]);
+}
+
+#[test]
+fn test_distance_transform() {
+    let mut map = TaxicabMap::rectangle(7, 5, &'.');
+    map.set_point(1, 1, '#');
+    map.set_point(2, 1, '#');
+    let distance = map.distance_transform(|_, _, v| *v == '#');
+    assert_eq!(distance[(1, 1)], 0);
+    assert_eq!(distance[(6, 4)], 7);
+    assert_eq!(distance[(0, 0)], 2);
+    let torus = map.clone().with_cycle(true, true);
+    let distance = torus.distance_transform(|_, _, v| *v == '#');
+    assert_eq!(distance[(6, 4)], 4, "wraps across both seams");
+    for (x, y, d) in distance.points_all() {
+        let brute = torus.points_all().filter(|(_, _, v)| **v == '#').map(|(fx, fy, _)| {
+            let dx = (x - fx).rem_euclid(7).min((fx - x).rem_euclid(7));
+            let dy = (y - fy).rem_euclid(5).min((fy - y).rem_euclid(5));
+            (dx + dy) as usize
+        });
+        assert_eq!(Some(*d), brute.min());
+    }
+    let signed = map.signed_distance_transform(|_, _, v| *v == '#');
+    assert_eq!((signed[(1, 1)], signed[(3, 1)], signed[(4, 1)]), (-1, 1, 2));
+    assert!(TaxicabMap::square(3, &0).distance_transform(|_, _, _| false).points_all().all(|(_, _, d)| *d == usize::MAX));
+}
+
+#[test]
+fn test_voronoi() {
+    let map = TaxicabMap::rectangle(7, 3, &'.');
+    let labels = map.voronoi(&[(0, 1), (6, 1)]);
+    assert_eq!(labels[(2, 0)], Some(0));
+    assert_eq!(labels[(3, 1)], Some(0), "ties go to the lowest seed");
+    assert_eq!(labels[(4, 2)], Some(1));
+    let torus = map.clone().with_cycle(true, false);
+    assert_eq!(torus.taxicab_distance((0, 0), (6, 2)), 3);
+    let borders = labels.region_borders();
+    assert_eq!(borders.len(), 6, "three border edges seen from both sides");
+    assert!(borders.contains(&Joint::new(3, 0, Direction::X(true))));
+    assert!(borders.contains(&Joint::new(4, 0, Direction::X(false))));
+    // a wall with a gap at the top forces the left seed around it
+    let mut walled = map.clone();
+    walled.set_point(2, 1, '#');
+    walled.set_point(2, 2, '#');
+    let labels = walled.voronoi_path(&[(1, 2), (5, 2)], |_, _, v| *v != '#', |_, _, _| 1.0);
+    assert_eq!(labels[(2, 1)], None);
+    assert_eq!(labels[(3, 2)], Some(1));
+    assert_eq!(labels[(2, 0)], Some(0));
+    assert_eq!(labels[(3, 0)], Some(0), "both seeds reach it in four steps");
+    assert_eq!(labels[(4, 0)], Some(1));
+}
+
+#[test]
+fn test_critical() {
+    // two rooms joined by a one tile corridor
+    let mut map = TaxicabMap::rectangle(7, 3, &'.');
+    map.set_point(3, 0, '#');
+    map.set_point(3, 2, '#');
+    let passable = |_: isize, _: isize, v: &char| *v != '#';
+    let points = map.articulation_points(passable);
+    assert_eq!(points.into_iter().collect_vec(), vec![(2, 1), (3, 1), (4, 1)]);
+    let bridges = map.bridges(passable);
+    assert_eq!(
+        bridges.into_iter().collect_vec(),
+        vec![Joint::new(2, 1, Direction::X(true)), Joint::new(3, 1, Direction::X(true))]
+    );
+    let cylinder = map.clone().with_cycle(true, false);
+    assert!(cylinder.articulation_points(passable).is_empty(), "the seam is a second way around");
+    assert!(cylinder.bridges(passable).is_empty());
+    let narrow = TaxicabMap::rectangle(2, 1, &'.').with_cycle(true, false);
+    assert!(narrow.bridges(passable).is_empty(), "two parallel joints across the seam");
+    let widths = TaxicabMap::rectangle(7, 3, &'.').voronoi(&[(0, 1), (6, 1)]).border_widths();
+    assert_eq!(widths.into_iter().collect_vec(), vec![((0, 1), 3)]);
+    let widths = map.voronoi_path(&[(0, 1), (6, 1)], passable, |_, _, _| 1.0).border_widths();
+    assert_eq!(widths.into_iter().collect_vec(), vec![((0, 1), 1)]);
+}
+
+#[test]
+fn test_influence() {
+    let mut threat = TaxicabMap::rectangle(5, 5, &0.0);
+    threat.stamp(0, 0, -4.0, Decay::Linear(1.0));
+    assert_eq!((threat[(0, 0)], threat[(1, 1)], threat[(2, 2)]), (-4.0, -2.0, 0.0));
+    let mut goal = TaxicabMap::rectangle(5, 5, &0.0);
+    goal.stamp(4, 4, 8.0, Decay::Exponential(0.5));
+    assert_eq!((goal[(4, 4)], goal[(3, 4)], goal[(2, 3)]), (8.0, 4.0, 1.0));
+    let mut total = threat.clone();
+    total.combine(&goal, Combine::Add);
+    assert_eq!(total[(0, 0)], -4.0 + 8.0 / 256.0);
+    let mut worst = threat.clone();
+    worst.combine(&goal, Combine::Min);
+    assert_eq!((worst[(0, 0)], worst[(4, 4)]), (-4.0, 0.0));
+    total.normalize();
+    assert_eq!((total[(0, 0)], total[(4, 4)]), (0.0, 1.0));
+    assert_eq!(total.best_point(0..5, 0..5), Some((4, 4)));
+    assert_eq!(total.best_point(0..2, 0..2), Some((1, 1)));
+    assert_eq!(threat.best_point(0..5, 0..5), Some((0, 4)), "ties go to the lowest x then y");
+    // the wall makes the far side of it further away by path
+    let mut terrain = TaxicabMap::rectangle(5, 3, &'.');
+    terrain.set_point(1, 0, '#');
+    terrain.set_point(1, 1, '#');
+    let field = terrain.action_field((0, 0), f64::INFINITY).with_passable(|_, _, v| *v != '#');
+    let mut scent = TaxicabMap::rectangle(5, 3, &0.0);
+    scent.stamp_field(field, 5.0, Decay::Linear(1.0));
+    assert_eq!((scent[(0, 0)], scent[(2, 2)], scent[(2, 0)], scent[(1, 0)]), (5.0, 1.0, 0.0, 0.0));
+}
+
+#[test]
+fn test_kernel() {
+    assert_eq!(Kernel::diamond(2).weights().len(), 13);
+    assert_eq!(Kernel::cross(2).weights().len(), 9);
+    let mut map = TaxicabMap::rectangle(5, 1, &0.0);
+    map.set_point(0, 0, 3.0);
+    let sum = Kernel::new(vec![(-1, 0, 1.0), (1, 0, 1.0)]);
+    let clamp = map.convolve(&sum, &Boundary::Clamp);
+    assert_eq!(clamp.points_all().map(|(_, _, v)| *v).collect_vec(), vec![3.0, 3.0, 0.0, 0.0, 0.0]);
+    let wrap = map.convolve(&sum, &Boundary::Wrap);
+    assert_eq!(wrap.points_all().map(|(_, _, v)| *v).collect_vec(), vec![0.0, 3.0, 0.0, 0.0, 3.0]);
+    let constant = map.convolve(&sum, &Boundary::Constant(1.0));
+    assert_eq!(constant.points_all().map(|(_, _, v)| *v).collect_vec(), vec![1.0, 3.0, 0.0, 0.0, 1.0]);
+    assert_eq!(map.clone().with_cycle(true, false).convolve(&sum, &Boundary::Clamp), wrap.with_cycle(true, false));
+    let blur = TaxicabMap::rectangle(3, 3, &9.0).blur(1);
+    assert!(blur.points_all().all(|(_, _, v)| (*v - 9.0).abs() < 1e-9));
+    // morphology fast path agrees with the generic kernel
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut noise = TaxicabMap::rectangle(12, 9, &false);
+    for (_, _, v) in noise.points_mut() {
+        *v = rng.gen_bool(0.4)
+    }
+    let diamond = Kernel::diamond(2);
+    let dilate = noise.apply_kernel(&diamond, &Boundary::Clamp, |_, _, _, s| s.iter().any(|(_, v)| **v));
+    let erode = noise.apply_kernel(&diamond, &Boundary::Clamp, |_, _, _, s| s.iter().all(|(_, v)| **v));
+    assert_eq!(noise.dilate(2), dilate);
+    assert_eq!(noise.erode(2), erode);
+    let mut dot = TaxicabMap::rectangle(5, 5, &true);
+    dot.set_point(2, 2, false);
+    assert_eq!(dot.close(1), TaxicabMap::rectangle(5, 5, &true), "closing fills the pinhole");
+    assert_eq!(dot.map_values(|_, _, v| !v).open(1), TaxicabMap::rectangle(5, 5, &false), "opening removes the speck");
+}
+
+#[test]
+fn test_text() {
+    let text = "
+#...
+#.#.
+....
+";
+    let map = TaxicabMap::parse_text(text, |c| Some(c == '#')).unwrap();
+    assert_eq!(map.get_size(), (4, 3));
+    assert_eq!((map[(0, 0)], map[(0, 2)], map[(2, 1)], map[(2, 2)]), (false, true, true, false));
+    let draw = |_: isize, _: isize, v: &bool| if *v { '#' } else { '.' };
+    assert_eq!(map.render_text(draw).to_string(), text.trim_start());
+    assert!(TaxicabMap::parse_text("..\n.", Some).is_none(), "ragged lines");
+    assert!(TaxicabMap::parse_text(".x", |c| (c == '.').then_some(())).is_none(), "rejected character");
+    let (path, _) = map.path_finder((1, 2), (3, 2)).with_passable(|_, _, v| !*v).solve_joint();
+    let drawn = map.render_text(draw).with_path(&path).to_string();
+    assert_eq!(drawn, "#→→.\n#.#.\n....\n");
+    let mut map = map.with_origin(-1, -1);
+    map.set_point_exists(2, 1, false);
+    assert_eq!(map.render_text(draw).with_axes(true).with_hole(' ').to_string(), "   1012\n 1 #.. \n 0 #.#.\n-1 ....\n");
+}
+
+#[test]
+fn test_image() {
+    let mut map = TaxicabMap::rectangle(3, 2, &0u8).with_origin(-1, 0);
+    map.set_point(1, 1, 200);
+    let gray = |_: isize, _: isize, v: &u8| [*v; 3];
+    let ppm = map.render_image(gray).to_ppm();
+    assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+    assert_eq!(&ppm[11..14], &[0, 0, 0]);
+    assert_eq!(&ppm[17..20], &[200, 200, 200], "the highest row is on top");
+    let back = TaxicabMap::from_ppm(&ppm, |[r, _, _]| Some(r)).unwrap();
+    assert_eq!(back, map.clone().with_origin(0, 0));
+    let pgm = map.render_image(gray).to_pgm();
+    assert_eq!(TaxicabMap::from_ppm(&pgm, |[r, _, _]| Some(r)).unwrap(), back);
+    assert!(TaxicabMap::from_ppm(b"P6\n# comment\n1 1\n255\n\x01\x02\x03", Some).is_some());
+    assert!(TaxicabMap::from_ppm(b"P6\n1 1\n255\n\x01\x02", Some).is_none(), "truncated raster");
+    assert!(TaxicabMap::from_ppm(b"P6\n4294967296 4294967296\n255\n\x01", Some).is_none(), "overflowing size");
+    assert!(TaxicabMap::from_ppm(b"P5\n0 99999999999\n255\n", Some).is_some_and(|map| map.count_points() == 0));
+    let red = [255, 0, 0];
+    let path = [Joint::new(-1, 0, Direction::X(true))];
+    let (width, height, pixels) =
+        map.render_image(gray).with_scale(3).with_path(&path, red).with_origin_marker([0, 0, 255]).pixels();
+    assert_eq!((width, height), (9, 6));
+    let at = |x: usize, y: usize| pixels[y * width + x];
+    assert_eq!((at(0, 4), at(1, 4), at(2, 4), at(1, 3)), ([0; 3], red, red, [0; 3]));
+    assert_eq!((at(3, 3), at(4, 4), at(5, 5)), ([0, 0, 255], [0; 3], [0, 0, 255]), "origin framed");
+    #[cfg(feature = "png")]
+    {
+        let png = map.render_image(gray).to_png().unwrap();
+        assert_eq!(TaxicabMap::from_png(&png, |[r, _, _]| Some(r)).unwrap(), back);
+    }
+}
+
+#[test]
+#[cfg(feature = "tiled")]
+fn test_tiled() {
+    use taxicab_map::{TiledMap, TiledObject, TiledTileset};
+    let finite = r#"{
+        "orientation": "orthogonal", "tilewidth": 16, "tileheight": 16, "width": 3, "height": 2, "infinite": false,
+        "tilesets": [
+            { "firstgid": 1, "source": "terrain.tsx" },
+            { "firstgid": 5, "name": "props", "tilewidth": 16, "tileheight": 16, "tilecount": 4, "columns": 2,
+              "image": "props.png", "imagewidth": 32, "imageheight": 32, "margin": 0, "spacing": 0 }
+        ],
+        "layers": [
+            { "type": "tilelayer", "name": "ground", "x": 0, "y": 0, "width": 3, "height": 2, "data": [1, 2, 3, 4, 5, 6] },
+            { "type": "group", "name": "group", "layers": [
+                { "type": "objectgroup", "name": "spawns", "objects": [
+                    { "name": "player", "point": true, "x": 8, "y": 24 },
+                    { "name": "room", "x": 16, "y": 0, "width": 32, "height": 16 }
+                ] }
+            ] }
+        ]
+    }"#;
+    let tiled = TiledMap::from_json(finite).unwrap();
+    let ground = tiled.get_layer("ground").unwrap();
+    assert_eq!(ground.get_range(), (0..3, -1..1));
+    assert_eq!((ground[(0, 0)], ground[(2, 0)], ground[(0, -1)]), (1, 3, 4));
+    let spawns = tiled.get_objects("spawns").unwrap();
+    assert_eq!(spawns[0], TiledObject::Point { name: "player".to_string(), x: 0.5, y: -0.5 });
+    assert_eq!(spawns[1], TiledObject::Rect { name: "room".to_string(), x: 1.0, y: 0.0, width: 2.0, height: 1.0 });
+    assert_eq!(tiled.tilesets().map(|t| t.get_first_gid()).collect_vec(), vec![1, 5]);
+    assert_eq!(tiled.tilesets().next(), Some(&TiledTileset::External { first_gid: 1, source: "terrain.tsx".to_string() }));
+    assert_eq!(TiledMap::from_json(&tiled.to_json()).unwrap(), tiled);
+    assert_eq!(TiledMap::from_tmx(&tiled.to_tmx()).unwrap(), tiled);
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="8" tileheight="8" infinite="0">
+ <tileset firstgid="1" source="walls &amp; floors.tsx"/>
+ <layer id="1" name="ground" width="2" height="2">
+  <data>
+   <tile gid="1"/><tile/><tile gid="2"/><tile gid="3"/>
+  </data>
+ </layer>
+ <group id="2" name="group">
+  <objectgroup id="3" name="spawns">
+   <object id="1" name="player" x="4" y="12"><point/></object>
+  </objectgroup>
+ </group>
+</map>"#;
+    let tiled = TiledMap::from_tmx(tmx).unwrap();
+    assert_eq!(
+        tiled.tilesets().next(),
+        Some(&TiledTileset::External { first_gid: 1, source: "walls & floors.tsx".to_string() })
+    );
+    let ground = tiled.get_layer("ground").unwrap();
+    assert_eq!((ground[(0, 0)], ground[(1, 0)], ground[(0, -1)]), (1, 0, 2));
+    assert_eq!(tiled.get_objects("spawns").unwrap()[0], TiledObject::Point { name: "player".to_string(), x: 0.5, y: -0.5 });
+    assert_eq!(TiledMap::from_json(&tiled.to_json()).unwrap(), tiled);
+    assert!(TiledMap::from_tmx(&tmx.replace("<data>", r#"<data encoding="base64">"#)).is_none());
+    let infinite = r#"{
+        "orientation": "orthogonal", "tilewidth": 8, "tileheight": 8, "infinite": true,
+        "layers": [{ "type": "tilelayer", "name": "walls", "chunks": [
+            { "x": -2, "y": -2, "width": 2, "height": 2, "data": [1, 0, 0, 2] },
+            { "x": 0, "y": 0, "width": 2, "height": 1, "data": [3, 4] }
+        ] }]
+    }"#;
+    let tiled = TiledMap::from_json(infinite).unwrap();
+    let walls = tiled.get_layer("walls").unwrap();
+    assert_eq!(walls.get_range(), (-2..2, 0..3), "origin from the chunk offsets");
+    assert_eq!((walls[(-2, 2)], walls[(-1, 1)], walls[(1, 0)], walls[(0, 2)]), (1, 2, 4, 0));
+    let json = tiled.to_json();
+    assert!(json.contains(r#""infinite":true"#));
+    let back = TiledMap::from_json(&json).unwrap();
+    let xml = TiledMap::from_tmx(&tiled.to_tmx()).unwrap();
+    assert_eq!(xml, back);
+    for (x, y, gid) in walls.points_all() {
+        assert_eq!(back.get_layer("walls").unwrap().get_point(x, y), Some(gid));
+    }
+    // sizes are checked before allocating
+    let huge = r#"{ "tilewidth": 8, "tileheight": 8, "layers": [
+        { "type": "tilelayer", "width": 4294967296, "height": 4294967296, "data": [] }
+    ] }"#;
+    assert!(TiledMap::from_json(huge).is_none());
+    let spread = r#"{ "tilewidth": 8, "tileheight": 8, "layers": [{ "type": "tilelayer", "chunks": [
+        { "x": -9000000000000000000, "y": 0, "width": 1, "height": 1, "data": [1] },
+        { "x": 9000000000000000000, "y": 0, "width": 1, "height": 1, "data": [1] }
+    ] }] }"#;
+    assert!(TiledMap::from_json(spread).is_none());
+    let sparse = r#"{ "tilewidth": 8, "tileheight": 8, "layers": [{ "type": "tilelayer", "chunks": [
+        { "x": 0, "y": 0, "width": 1, "height": 1, "data": [1] },
+        { "x": 100000, "y": 100000, "width": 1, "height": 1, "data": [1] }
+    ] }] }"#;
+    assert!(TiledMap::from_json(sparse).is_none(), "far more area than chunks");
+}
+
+#[test]
+fn test_binary() {
+    let mut map = TaxicabMap::rectangle(40, 30, &0u16).with_origin(-7, 3).with_topology(Topology::Mobius);
+    for x in -7..10 {
+        map.set_point(x, 10, 513);
+    }
+    map.set_point_exists(0, 5, false);
+    map.add_portal(Joint::new(2, 4, Direction::X(true)), Portal::new(Joint::new(20, 20, Direction::Y(true))).with_cost(2.5));
+    let mut bytes = vec![];
+    map.write_binary(&mut bytes, |v| v.to_le_bytes().to_vec()).unwrap();
+    assert!(bytes.starts_with(b"TXMP\x01"));
+    assert!(bytes.len() < 200, "runs keep {} bytes small", bytes.len());
+    let decode = |b: &[u8]| Some(u16::from_le_bytes(b.try_into().ok()?));
+    let back = TaxicabMap::read_binary(bytes.as_slice(), decode).unwrap();
+    assert_eq!(back, map);
+    assert!(!back.has_point(0, 5));
+    let mut corrupt = bytes.clone();
+    corrupt[20] ^= 1;
+    assert!(TaxicabMap::read_binary(corrupt.as_slice(), decode).is_err());
+    assert!(TaxicabMap::read_binary(&bytes[..bytes.len() - 1], decode).is_err(), "truncated");
+    let reject = |_: &[u8]| None::<u16>;
+    assert!(TaxicabMap::read_binary(bytes.as_slice(), reject).is_err());
+    // huge sizes and counts in a tiny stream fail without reserving memory for them
+    let huge =
+        b"TXMP\x01\x00\x00\x00\x80\x80\x80\x80\x04\x80\x80\x80\x80\x04\x80\x80\x80\x80\x80\x01\x80\x80\x80\x80\x80\x01\x01\x02";
+    assert!(TaxicabMap::read_binary(huge.as_slice(), decode).is_err());
+    // one run may cover the whole area, the area limit bounds what a short stream allocates
+    assert!(TaxicabMap::read_binary_limited(bytes.as_slice(), 40 * 30 - 1, decode).is_err());
+    assert_eq!(TaxicabMap::read_binary_limited(bytes.as_slice(), 40 * 30, decode).unwrap(), map);
+}
+
+#[test]
+fn test_csv() {
+    let mut map = TaxicabMap::rectangle(3, 2, &1.5).with_origin(-1, 4);
+    map.set_point(1, 5, 2.0);
+    map.set_point_exists(0, 4, false);
+    let plain = map.to_csv(&CsvFormat::default());
+    assert_eq!(plain, "1.5,1.5,2\n1.5,,1.5\n");
+    let bottom = CsvFormat::default().with_reverse(true).with_delimiter(';');
+    assert_eq!(map.to_csv(&bottom), "1.5;;1.5\n1.5;1.5;2\n");
+    let back = TaxicabMap::<f64>::from_csv(&plain, &CsvFormat::default()).unwrap();
+    assert_eq!(back, map.clone().with_origin(0, 0));
+    assert_eq!(TaxicabMap::<f64>::from_csv(&map.to_csv(&bottom), &bottom).unwrap(), back);
+    let header = CsvFormat::default().with_header(true);
+    let text = map.to_csv(&header);
+    assert_eq!(text, ",-1,0,1\n5,1.5,1.5,2\n4,1.5,,1.5\n");
+    assert_eq!(TaxicabMap::<f64>::from_csv(&text, &header).unwrap(), map, "origin round-trips");
+    assert!(TaxicabMap::<f64>::from_csv("1,2\n3", &CsvFormat::default()).is_none(), "ragged");
+    let blank = TaxicabMap::<u8>::from_csv("1,2\n3,4\n\n \n", &CsvFormat::default()).unwrap();
+    assert_eq!(blank.get_size(), (2, 2), "blank lines at the end are not rows");
+    assert!(TaxicabMap::<f64>::from_csv("1,x", &CsvFormat::default()).is_none());
+    assert!(TaxicabMap::<f64>::from_csv(",0,2\n0,1,1", &header).is_none(), "gap in the header");
+    // rows made only of holes are kept, with whitespace delimiters and one point wide maps
+    let mut map = TaxicabMap::rectangle(3, 3, &1u8);
+    (0..3).for_each(|x| assert!(map.set_point_exists(x, 1, false)));
+    let tabs = CsvFormat::default().with_delimiter('\t');
+    assert_eq!(map.to_csv(&tabs), "1\t1\t1\n\t\t\n1\t1\t1\n");
+    assert_eq!(TaxicabMap::<u8>::from_csv(&map.to_csv(&tabs), &tabs).unwrap(), map);
+    let mut map = TaxicabMap::rectangle(1, 3, &1u8);
+    map.set_point_exists(0, 1, false);
+    assert_eq!(TaxicabMap::<u8>::from_csv(&map.to_csv(&CsvFormat::default()), &CsvFormat::default()).unwrap(), map);
+}
+
+#[test]
+fn test_diff() {
+    let mut old = TaxicabMap::rectangle(4, 3, &0u8);
+    old.set_point(1, 1, 7);
+    let mut new = old.clone();
+    new.set_point(2, 2, 9);
+    new.set_point_exists(1, 1, false);
+    new.extend(Direction::X(true), 2, &5);
+    let diff = old.diff(&new);
+    let mut applied = old.clone();
+    assert!(applied.apply_diff(&diff));
+    assert_eq!(applied, new);
+    assert!(matches!(diff.edits()[0], MapEdit::Bounds(..)));
+    // moving the whole map only needs the origin
+    let moved = old.diff(&old.clone().with_origin(10, -3));
+    assert_eq!(moved.edits(), &[MapEdit::Origin(10, -3)]);
+    assert!(old.diff(&old).is_empty());
+    let mut bytes = vec![];
+    diff.write_binary(&mut bytes, |v| vec![*v]).unwrap();
+    let back = MapDiff::read_binary(bytes.as_slice(), |b| b.first().copied()).unwrap();
+    assert_eq!(back, diff);
+    // the journal produces the same result without comparing the maps
+    let mut journaled = old.clone().with_growth(GrowthPolicy::Exact, 0);
+    journaled.start_journal();
+    journaled.set_point(0, 0, 3);
+    journaled[(3, 2)] = 4;
+    journaled.set_point(5, 1, 6);
+    let diff = journaled.journal_diff().unwrap();
+    let mut replay = old.clone();
+    assert!(replay.apply_diff(&diff));
+    assert_eq!(replay.with_growth(GrowthPolicy::Exact, 0), journaled);
+    assert!(journaled.journal_diff().unwrap().is_empty(), "taking the journal clears it");
+    // values move with the origin, reshaping keeps them in place
+    let mut line = TaxicabMap::rectangle(3, 1, &0u8);
+    (0..3).for_each(|x| assert!(line.set_point(x, 0, x as u8 + 1)));
+    let mut moved = line.clone();
+    moved.start_journal();
+    moved.set_origin(1, 0);
+    moved.set_point(2, 0, 9);
+    moved.extend(Direction::X(true), 2, &0);
+    moved.shift_origin(0, -3);
+    let diff = moved.journal_diff().unwrap();
+    assert_eq!(diff.edits()[0], MapEdit::Origin(1, -3));
+    let mut replay = line.clone();
+    assert!(replay.apply_diff(&diff));
+    assert_eq!(replay, moved);
+    // bounds that are reversed, overflow or are too large are refused without touching the map
+    for bounds in [(std::ops::Range { start: 3, end: 0 }, 0..1), (isize::MIN..isize::MAX, 0..1), (0..1 << 20, 0..1 << 20)] {
+        let mut diff = MapDiff::new();
+        diff.push(MapEdit::Bounds(bounds.0, bounds.1, 0));
+        assert!(!replay.apply_diff(&diff));
+        assert_eq!(replay, moved);
+    }
+    journaled.stop_journal();
+    assert!(journaled.journal_diff().is_none());
+}
+
+#[test]
+fn test_history() {
+    let mut history = MapHistory::new(TaxicabMap::rectangle(3, 3, &'.')).with_limit(3);
+    history.set_point(0, 0, '#');
+    history.begin("room");
+    history.set_point(1, 1, 'a');
+    history.set_point(1, 1, 'b');
+    history.extend(Direction::X(true), 2, &'~');
+    history.commit();
+    assert_eq!(history.get_map().get_range(), (-2..3, 0..3));
+    assert_eq!(history.undo_names().collect_vec(), vec!["set_point", "room"]);
+    assert_eq!(history.undo(), Some("room"));
+    assert_eq!(history.get_map().get_range(), (0..3, 0..3));
+    assert_eq!(history.get_map()[(1, 1)], '.');
+    assert_eq!(history.get_map()[(0, 0)], '#');
+    assert_eq!(history.redo(), Some("room"));
+    assert_eq!((history.get_map()[(1, 1)], history.get_map()[(-1, 0)]), ('b', '~'));
+    // shrinking drops cells that undo brings back
+    history.resize(1, 1, Anchor::End, Anchor::End, &'.');
+    history.undo();
+    assert_eq!(history.get_map()[(0, 0)], '#');
+    assert_eq!(history.get_map().get_range(), (-2..3, 0..3));
+    // a new edit drops the redo history
+    history.undo();
+    let mut stamp = TaxicabMap::rectangle(2, 1, &'@');
+    stamp.set_origin(5, 5);
+    history.paste(&stamp, -5, -4);
+    assert_eq!(history.redo_names().count(), 0);
+    assert_eq!(history.redo(), None);
+    assert_eq!((history.get_map()[(0, 1)], history.get_map()[(1, 1)], history.get_map()[(2, 1)]), ('@', '@', '.'));
+    history.set_point(2, 2, '!');
+    history.set_point(2, 1, '!');
+    assert_eq!(history.undo_names().collect_vec(), vec!["paste", "set_point", "set_point"], "bounded to the limit");
+    while history.undo().is_some() {}
+    assert_eq!(history.get_map()[(0, 0)], '#', "the oldest edit is out of reach");
+    assert_eq!(history.get_map()[(1, 1)], '.');
+    // holes and growth are restored by undo and redo
+    let mut map = TaxicabMap::rectangle(3, 2, &0u8).with_growth(GrowthPolicy::Exact, 0);
+    map.set_point_exists(2, 1, false);
+    map.set_point(2, 0, 4);
+    let mut history = MapHistory::new(map.clone());
+    history.resize(2, 2, Anchor::Start, Anchor::Start, &0);
+    history.set_point(0, 4, 7);
+    let grown = history.get_map().clone();
+    assert_eq!(grown.get_range(), (0..2, 0..5));
+    history.undo();
+    history.undo();
+    assert_eq!(history.get_map(), &map);
+    history.redo();
+    history.redo();
+    assert_eq!(history.get_map(), &grown);
+}
+
+#[test]
+fn test_tracker() {
+    use std::{cell::RefCell, rc::Rc};
+    let mut map = TrackedMap::new(TaxicabMap::rectangle(6, 4, &0));
+    let events = Rc::new(RefCell::new(vec![]));
+    let sink = events.clone();
+    map.subscribe(move |x, y, old: &i32, new: &i32| sink.borrow_mut().push((x, y, *old, *new)));
+    assert!(map.take_dirty().is_empty());
+    map[(1, 1)] = 5;
+    map[(2, 1)] = 5;
+    map[(1, 2)] = 5;
+    map[(2, 2)] = 5;
+    assert!(map.set_point(5, 0, 7));
+    assert!(!map.set_point(9, 9, 7), "the map never grows");
+    // written back to the old value
+    *map.mut_point(4, 3).unwrap() = 1;
+    *map.mut_point(4, 3).unwrap() = 0;
+    assert!(map.is_touched());
+    assert_eq!(map.take_dirty(), vec![(5..6, 0..1), (1..3, 1..3)]);
+    assert!(!map.is_touched());
+    assert_eq!(events.borrow().len(), 5);
+    assert!(events.borrow().contains(&(5, 0, 0, 7)));
+    events.borrow_mut().clear();
+    for (x, _, v) in map.points_mut() {
+        if x == 0 {
+            *v = 9;
+        }
+    }
+    assert_eq!(map.take_dirty(), vec![(0..1, 0..4)]);
+    assert_eq!(events.borrow().len(), 4);
+    assert_eq!(map[(0, 3)], 9);
+    assert_eq!(map.get_point(1, 1), Some(&5));
+    // only the yielded points are kept
+    if let Some((_, _, v)) = map.points_mut().next() {
+        *v = 3;
+    }
+    assert_eq!(map.take_dirty(), vec![(0..1, 0..1)]);
+}
+
+#[test]
+fn test_serde_baseline() {
+    // a map serialized before twists, masks and portals existed
+    let json = r#"{"dense":{"v":1,"dim":[2,2],"data":[1,2,3,4]},"cycle_x":true,"cycle_y":false,"origin_x":-1,"origin_y":0}"#;
+    let map: TaxicabMap<u8> = serde_json::from_str(json).unwrap();
+    assert_eq!(map.get_range(), (-1..1, 0..2));
+    assert_eq!(map.get_cycle(), (true, false));
+    assert_eq!(map.get_twist(), (false, false));
+    assert!(!map.is_masked());
+    assert_eq!(map.portals().count(), 0);
+    assert_eq!(map[(-1, 1)], 2);
+    let back: TaxicabMap<u8> = serde_json::from_str(&serde_json::to_string(&map).unwrap()).unwrap();
+    assert_eq!(back, map);
+}